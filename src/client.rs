@@ -0,0 +1,2076 @@
+//! HTTP execution core: build a request from a [`RequestSpec`], send it, and
+//! turn the reply into an [`HttpResponse`]. Has no GUI dependencies, so it can
+//! be used on its own:
+//!
+//! ```no_run
+//! # async fn run() {
+//! use crabipie::client::{execute, HttpMethod, RequestSpec};
+//!
+//! let response = execute(RequestSpec::new(HttpMethod::GET, "https://example.com")).await;
+//! println!("{}\n{}", response.status, response.body);
+//! # }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum HttpMethod {
+    GET,
+    POST,
+    PUT,
+    DELETE,
+    PATCH,
+    /// Asks the server to echo the request back, to see what proxies changed
+    TRACE,
+    /// Asks the server to open a tunnel to the URL's `host:port`
+    CONNECT,
+}
+
+impl HttpMethod {
+    pub const ALL: [HttpMethod; 7] = [
+        HttpMethod::GET,
+        HttpMethod::POST,
+        HttpMethod::PUT,
+        HttpMethod::DELETE,
+        HttpMethod::PATCH,
+        HttpMethod::TRACE,
+        HttpMethod::CONNECT,
+    ];
+
+    /// Whether the UI offers a body editor for this method.
+    pub fn has_body(&self) -> bool {
+        matches!(self, HttpMethod::POST | HttpMethod::PUT | HttpMethod::PATCH)
+    }
+
+    pub fn as_reqwest(&self) -> reqwest::Method {
+        match self {
+            HttpMethod::GET => reqwest::Method::GET,
+            HttpMethod::POST => reqwest::Method::POST,
+            HttpMethod::PUT => reqwest::Method::PUT,
+            HttpMethod::DELETE => reqwest::Method::DELETE,
+            HttpMethod::PATCH => reqwest::Method::PATCH,
+            HttpMethod::TRACE => reqwest::Method::TRACE,
+            HttpMethod::CONNECT => reqwest::Method::CONNECT,
+        }
+    }
+}
+
+impl std::str::FromStr for HttpMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "GET" => Ok(HttpMethod::GET),
+            "POST" => Ok(HttpMethod::POST),
+            "PUT" => Ok(HttpMethod::PUT),
+            "DELETE" => Ok(HttpMethod::DELETE),
+            "PATCH" => Ok(HttpMethod::PATCH),
+            "TRACE" => Ok(HttpMethod::TRACE),
+            "CONNECT" => Ok(HttpMethod::CONNECT),
+            other => Err(format!("unsupported method: {}", other)),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum ContentType {
+    Json,
+    /// Raw body whose Content-Type is sniffed from the text at send time
+    Auto,
+    FormData,
+    FormUrlEncoded,
+}
+
+impl ContentType {
+    /// Whether the request carries the raw `body` text rather than form fields.
+    pub fn is_raw(&self) -> bool {
+        matches!(self, ContentType::Json | ContentType::Auto)
+    }
+}
+
+/// Guesses a Content-Type from the body text: a leading `{` or `[` is JSON,
+/// a leading `<` is XML and anything else is plain text.
+pub fn detect_body_mime(body: &str) -> &'static str {
+    match body
+        .trim_start_matches('\u{feff}')
+        .trim_start()
+        .chars()
+        .next()
+    {
+        Some('{' | '[') => "application/json",
+        Some('<') => "application/xml",
+        _ => "text/plain",
+    }
+}
+
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum PatchFormat {
+    Json,
+    MergePatch,
+    JsonPatch,
+}
+
+impl PatchFormat {
+    pub fn mime(&self) -> &'static str {
+        match self {
+            PatchFormat::Json => "application/json",
+            PatchFormat::MergePatch => "application/merge-patch+json",
+            PatchFormat::JsonPatch => "application/json-patch+json",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub enum FormFieldType {
+    Text,
+    File,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct FormField {
+    pub key: String,
+    pub value: String,
+    pub files: Vec<String>,
+    pub field_type: FormFieldType,
+}
+
+impl Default for FormField {
+    fn default() -> Self {
+        Self {
+            key: String::new(),
+            value: String::new(),
+            files: Vec::new(),
+            field_type: FormFieldType::Text,
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum AuthType {
+    None,
+    Bearer,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CookieRow {
+    pub enabled: bool,
+    pub name: String,
+    pub value: String,
+}
+
+impl Default for CookieRow {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            name: String::new(),
+            value: String::new(),
+        }
+    }
+}
+
+/// A query parameter appended to the request URL when enabled.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct QueryParam {
+    pub enabled: bool,
+    pub name: String,
+    pub value: String,
+}
+
+impl Default for QueryParam {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            name: String::new(),
+            value: String::new(),
+        }
+    }
+}
+
+/// Everything needed to send one request. The GUI edits an instance of this
+/// directly; [`execute`] consumes it.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestSpec {
+    pub method: HttpMethod,
+    pub url: String,
+    /// Appended to `url`'s own query string, see [`RequestSpec::full_url`]
+    pub params: Vec<QueryParam>,
+    /// `Key: Value` lines; blank lines and `#` comments are ignored
+    pub headers: String,
+    /// Raw body sent when `content_type` is JSON or Auto
+    pub body: String,
+    pub content_type: ContentType,
+    /// Content-Type used for JSON bodies on PATCH
+    pub patch_format: PatchFormat,
+    /// Fields sent when `content_type` is one of the form types
+    pub form_data: Vec<FormField>,
+    /// Boundary for multipart bodies; empty picks a random one per request
+    pub multipart_boundary: String,
+    pub auth_type: AuthType,
+    pub bearer_token: String,
+    pub cookies: Vec<CookieRow>,
+    pub timeout: Duration,
+    /// Trim header lines and the bearer token strictly, as in
+    /// [`parse_header_lines_with`]
+    pub strict_headers: bool,
+    /// Limit the upload and the download to this many bytes per second, to
+    /// simulate a slow client; `None` sends at full speed
+    pub throttle: Option<u64>,
+    /// Status code the response should have, e.g. `201`; empty expects none
+    pub expected_status: String,
+    /// JSON Schema the response body is checked against; empty skips the check
+    pub response_schema: String,
+}
+
+impl RequestSpec {
+    pub fn new(method: HttpMethod, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            params: Vec::new(),
+            headers: String::new(),
+            body: String::new(),
+            content_type: ContentType::Json,
+            patch_format: PatchFormat::Json,
+            form_data: Vec::new(),
+            multipart_boundary: String::new(),
+            auth_type: AuthType::None,
+            bearer_token: String::new(),
+            cookies: Vec::new(),
+            timeout: Duration::from_secs(30),
+            strict_headers: false,
+            throttle: None,
+            expected_status: String::new(),
+            response_schema: String::new(),
+        }
+    }
+
+    /// Content-Type sent with the raw body. PATCH requests use the selected
+    /// patch format for JSON bodies.
+    pub fn raw_body_mime(&self) -> &'static str {
+        let mime = match self.content_type {
+            ContentType::Auto => detect_body_mime(&self.body),
+            _ => "application/json",
+        };
+        if mime == "application/json" && self.method == HttpMethod::PATCH {
+            self.patch_format.mime()
+        } else {
+            mime
+        }
+    }
+
+    /// Appends a `key: value` header line.
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        if !self.headers.is_empty() && !self.headers.ends_with('\n') {
+            self.headers.push('\n');
+        }
+        self.headers.push_str(&format!("{}: {}", key, value));
+        self
+    }
+
+    /// Sets a raw JSON body.
+    pub fn json_body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self.content_type = ContentType::Json;
+        self
+    }
+
+    /// Sets form fields, sent as multipart when `multipart` is true and
+    /// urlencoded otherwise.
+    pub fn form(mut self, fields: Vec<FormField>, multipart: bool) -> Self {
+        self.form_data = fields;
+        self.content_type = if multipart {
+            ContentType::FormData
+        } else {
+            ContentType::FormUrlEncoded
+        };
+        self
+    }
+
+    pub fn bearer(mut self, token: impl Into<String>) -> Self {
+        self.auth_type = AuthType::Bearer;
+        self.bearer_token = token.into();
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// `url` with the enabled params appended, percent-encoded. A URL that
+    /// doesn't parse is returned as is so sending reports the error.
+    pub fn full_url(&self) -> String {
+        let url = self.url.trim();
+        let mut params = self
+            .params
+            .iter()
+            .filter(|p| p.enabled && !p.name.trim().is_empty())
+            .peekable();
+        if params.peek().is_none() {
+            return url.to_string();
+        }
+        let Ok(mut parsed) = reqwest::Url::parse(url) else {
+            return url.to_string();
+        };
+        parsed
+            .query_pairs_mut()
+            .extend_pairs(params.map(|p| (p.name.trim(), p.value.as_str())));
+        parsed.to_string()
+    }
+
+    /// Joins the enabled cookie rows into a `Cookie` header value.
+    pub fn cookie_header(&self) -> Option<String> {
+        let pairs: Vec<String> = self
+            .cookies
+            .iter()
+            .filter(|c| c.enabled && !c.name.trim().is_empty())
+            .map(|c| format!("{}={}", c.name.trim(), c.value.trim()))
+            .collect();
+
+        if pairs.is_empty() {
+            None
+        } else {
+            Some(pairs.join("; "))
+        }
+    }
+
+    /// The headers that will actually be sent: the parsed header lines plus
+    /// the bearer token and cookie rows.
+    pub fn header_map(&self) -> reqwest::header::HeaderMap {
+        let mut headers = parse_header_lines_with(&self.headers, self.strict_headers).headers;
+
+        // Add Bearer token to headers if set
+        let token = if self.strict_headers {
+            strict_trim(&self.bearer_token)
+        } else {
+            &self.bearer_token
+        };
+        if self.auth_type == AuthType::Bearer
+            && !token.is_empty()
+            && let Ok(header_value) =
+                reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+        {
+            headers.insert(reqwest::header::AUTHORIZATION, header_value);
+        }
+
+        // Merge editor cookies with any Cookie header written by hand
+        if let Some(cookies) = self.cookie_header() {
+            let combined = match headers
+                .get(reqwest::header::COOKIE)
+                .and_then(|v| v.to_str().ok())
+            {
+                Some(existing) => format!("{}; {}", existing, cookies),
+                None => cookies,
+            };
+            if let Ok(header_value) = reqwest::header::HeaderValue::from_str(&combined) {
+                headers.insert(reqwest::header::COOKIE, header_value);
+            }
+        }
+
+        headers
+    }
+}
+
+impl Default for RequestSpec {
+    fn default() -> Self {
+        RequestSpec::new(HttpMethod::GET, "")
+    }
+}
+
+/// A plain GET of the given URL.
+impl From<&str> for RequestSpec {
+    fn from(url: &str) -> Self {
+        RequestSpec::new(HttpMethod::GET, url)
+    }
+}
+
+impl From<String> for RequestSpec {
+    fn from(url: String) -> Self {
+        RequestSpec::new(HttpMethod::GET, url)
+    }
+}
+
+#[derive(Default, Debug)]
+pub struct HttpResponse {
+    pub status: String,
+    pub headers: String,
+    /// The same headers as `headers`, unformatted
+    pub header_map: reqwest::header::HeaderMap,
+    /// Trailer fields sent after a chunked body, formatted like `headers`;
+    /// empty when there were none
+    pub trailers: String,
+    /// Body text, with JSON pretty printed
+    pub body: String,
+    /// Body text exactly as decoded; empty for binary bodies and errors
+    pub raw_body: String,
+    pub is_binary: bool,
+    pub filename: String,
+    pub bytes: Vec<u8>,
+    pub content_type: String,
+    /// `Content-Encoding` the body arrived in, already decoded in `body`/`bytes`
+    pub content_encoding: String,
+    /// Why `content_encoding` was left undecoded; empty when it was decoded
+    pub encoding_note: String,
+    pub location: String,
+    /// URL the response came from, after any redirects; empty for errors
+    pub final_url: String,
+    /// Time until the full body was received
+    pub elapsed: Duration,
+    /// Time spent decoding and pretty printing the body after it arrived
+    pub format_time: Option<Duration>,
+    pub request_size: Option<usize>,
+    pub response_size: usize,
+    pub http_version: String,
+}
+
+impl HttpResponse {
+    fn cancelled() -> Self {
+        HttpResponse {
+            status: "Cancelled".to_string(),
+            body: "Request was cancelled".to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Sends `request` with a fresh client and returns the response. Failures are
+/// reported in the response with an `"Error"` status rather than as `Err`.
+pub async fn execute(request: RequestSpec) -> HttpResponse {
+    let client = reqwest::Client::new();
+    execute_with(&client, request, &AtomicBool::new(false)).await
+}
+
+/// Like [`execute`], but reuses `client` (and its connection pool) and gives up
+/// as soon as `cancel_flag` is set.
+pub async fn execute_with(
+    client: &reqwest::Client,
+    spec: RequestSpec,
+    cancel_flag: &AtomicBool,
+) -> HttpResponse {
+    // Check if cancelled before starting
+    if cancel_flag.load(Ordering::Relaxed) {
+        return HttpResponse::cancelled();
+    }
+
+    if let Some(response) = mock_response(&spec) {
+        return response;
+    }
+
+    let url = spec.full_url();
+    let timeout = spec.timeout;
+    let throttle = spec.throttle;
+    let headers = spec.header_map();
+    // Multipart bodies read files and zip directories, so build off the
+    // async workers
+    let blocking_client = client.clone();
+    let built = tokio::task::spawn_blocking(move || build_request(&blocking_client, spec, headers))
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    let request = match built {
+        Ok(request) => request,
+        Err(e) => {
+            return HttpResponse {
+                status: "Error".to_string(),
+                body: format!("Request failed: {}", e),
+                ..Default::default()
+            };
+        }
+    };
+
+    // Check cancellation again, building a large body can take a while
+    if cancel_flag.load(Ordering::Relaxed) {
+        return HttpResponse::cancelled();
+    }
+
+    let mut request = match request.build() {
+        Ok(request) => request,
+        Err(e) => {
+            return HttpResponse {
+                status: "Error".to_string(),
+                body: format!("Request failed: {}", e),
+                ..Default::default()
+            };
+        }
+    };
+    // Streaming bodies such as multipart have no size up front
+    let request_size = request.body().and_then(|b| b.as_bytes()).map(|b| b.len());
+    if let Some(rate) = throttle
+        && let Some(body) = request.body_mut().take()
+    {
+        *request.body_mut() = Some(reqwest::Body::wrap(Throttled::new(body, rate)));
+    }
+    let started = std::time::Instant::now();
+
+    match client.execute(request).await {
+        Ok(resp) => {
+            // Check cancellation after receiving response
+            if cancel_flag.load(Ordering::Relaxed) {
+                return HttpResponse::cancelled();
+            }
+
+            let status = format!(
+                "{} {}",
+                resp.status().as_u16(),
+                resp.status().canonical_reason().unwrap_or("")
+            );
+            let headers_map = resp.headers().clone();
+            let headers = format!("{:#?}", headers_map);
+            let http_version = format!("{:?}", resp.version());
+            let final_url = resp.url().to_string();
+
+            // Resolve a redirect target against the final URL so it can be opened
+            let location = headers_map
+                .get("location")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|l| resp.url().join(l).ok())
+                .map(|u| u.to_string())
+                .unwrap_or_default();
+
+            // Detect content type
+            let content_type = headers_map
+                .get("content-type")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("")
+                .to_string();
+
+            let is_binary = content_type.starts_with("image/")
+                || content_type.starts_with("application/pdf")
+                || content_type.starts_with("application/octet-stream")
+                || content_type.starts_with("video/")
+                || content_type.starts_with("audio/");
+
+            // Extract filename from Content-Disposition or URL
+            let filename = headers_map
+                .get("content-disposition")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|s| {
+                    s.split("filename=")
+                        .nth(1)
+                        .map(|f| f.trim_matches(|c| c == '"' || c == '\'').to_string())
+                })
+                .unwrap_or_else(|| url.split('/').next_back().unwrap_or("download").to_string());
+
+            // Still set when reqwest wasn't built with the matching decompression
+            // feature, in which case the body has to be decoded here
+            let content_encoding = headers_map
+                .get("content-encoding")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("")
+                .trim()
+                .to_ascii_lowercase();
+
+            let mut response_size = 0;
+            let mut trailers = String::new();
+            let mut encoding_note = String::new();
+            let mut received = None;
+            let mut format_time = None;
+            let (body, raw_body, bytes) =
+                match read_decoded(resp, &content_encoding, throttle).await {
+                    Ok(read) => {
+                        if cancel_flag.load(Ordering::Relaxed) {
+                            return HttpResponse::cancelled();
+                        }
+                        response_size = read.wire_size;
+                        received = Some(started.elapsed());
+                        if let Some(map) = read.trailers.filter(|t| !t.is_empty()) {
+                            trailers = format!("{:#?}", map);
+                        }
+                        encoding_note = read.encoding_note.unwrap_or_default();
+
+                        if is_binary {
+                            let body = format!(
+                                "Binary file ({} bytes)\n\nContent-Type: {}",
+                                read.bytes.len(),
+                                content_type
+                            );
+                            (body, String::new(), read.bytes)
+                        } else {
+                            let format_started = std::time::Instant::now();
+                            let body_text = decode_text(&content_type, &read.bytes);
+
+                            // Try to pretty print JSON. Parsing a large body is CPU-bound, so
+                            // keep it off the async worker threads.
+                            let raw_body = body_text.clone();
+                            let body = tokio::task::spawn_blocking(move || pretty_json(body_text))
+                                .await
+                                .unwrap_or_else(|e| format!("Error formatting body: {}", e));
+                            format_time = Some(format_started.elapsed());
+                            (body, raw_body, Vec::new())
+                        }
+                    }
+                    Err(e) if is_binary => (
+                        format!("Error reading binary data: {}", e),
+                        String::new(),
+                        Vec::new(),
+                    ),
+                    Err(e) => (
+                        format!("Error reading body: {}", e),
+                        String::new(),
+                        Vec::new(),
+                    ),
+                };
+
+            HttpResponse {
+                status,
+                headers,
+                header_map: headers_map,
+                trailers,
+                body,
+                raw_body,
+                is_binary,
+                filename,
+                bytes,
+                content_type,
+                content_encoding,
+                encoding_note,
+                location,
+                final_url,
+                elapsed: received.unwrap_or_else(|| started.elapsed()),
+                format_time,
+                request_size,
+                response_size,
+                http_version,
+            }
+        }
+        Err(e) => {
+            let error_msg = if e.is_timeout() {
+                format!("Request timed out after {} seconds", timeout.as_secs())
+            } else {
+                format!("Request failed: {}", e)
+            };
+
+            HttpResponse {
+                status: "Error".to_string(),
+                body: error_msg,
+                ..Default::default()
+            }
+        }
+    }
+}
+
+/// Builds `spec` exactly as [`execute_with`] would, then drops it unsent.
+/// Returns the body size when known, or why the request can't be built.
+pub fn build_without_sending(
+    client: &reqwest::Client,
+    spec: RequestSpec,
+) -> Result<Option<usize>, String> {
+    let headers = spec.header_map();
+    let request = build_request(client, spec, headers)?
+        .build()
+        .map_err(|e| e.to_string())?;
+    Ok(request.body().and_then(|b| b.as_bytes()).map(|b| b.len()))
+}
+
+struct ReadBody {
+    bytes: Vec<u8>,
+    /// Size as received, before undoing the content encoding
+    wire_size: usize,
+    trailers: Option<reqwest::header::HeaderMap>,
+    /// Set when the content encoding couldn't be undone
+    encoding_note: Option<String>,
+}
+
+/// Reads the whole body, at most `throttle` bytes per second, keeping any
+/// trailers that follow it, and undoes `content_encoding`.
+async fn read_decoded(
+    resp: reqwest::Response,
+    content_encoding: &str,
+    throttle: Option<u64>,
+) -> Result<ReadBody, String> {
+    use http_body_util::BodyExt;
+
+    let body = reqwest::Body::from(resp);
+    let collected = match throttle {
+        Some(rate) => Throttled::new(body, rate).collect().await,
+        None => body.collect().await,
+    }
+    .map_err(|e| e.to_string())?;
+    let trailers = collected.trailers().cloned();
+    let raw = collected.to_bytes();
+    let wire_size = raw.len();
+    let decoded = decode_content_encoding(content_encoding, raw.to_vec())?;
+    Ok(ReadBody {
+        bytes: decoded.bytes,
+        wire_size,
+        trailers,
+        encoding_note: decoded.note,
+    })
+}
+
+/// Passes a body through at no more than a fixed number of bytes per second,
+/// in slices of a tenth of a second's worth so progress stays smooth.
+struct Throttled<B> {
+    inner: B,
+    bytes_per_sec: u64,
+    started: Option<tokio::time::Instant>,
+    passed: u64,
+    /// Data taken from `inner` but not yet let through
+    pending: bytes::Bytes,
+    delay: Option<std::pin::Pin<Box<tokio::time::Sleep>>>,
+}
+
+impl<B> Throttled<B> {
+    fn new(inner: B, bytes_per_sec: u64) -> Self {
+        Throttled {
+            inner,
+            bytes_per_sec: bytes_per_sec.max(1),
+            started: None,
+            passed: 0,
+            pending: bytes::Bytes::new(),
+            delay: None,
+        }
+    }
+}
+
+impl<B> http_body::Body for Throttled<B>
+where
+    B: http_body::Body<Data = bytes::Bytes> + Unpin,
+{
+    type Data = bytes::Bytes;
+    type Error = B::Error;
+
+    fn poll_frame(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Result<http_body::Frame<Self::Data>, Self::Error>>> {
+        use std::task::Poll;
+
+        let this = &mut *self;
+        loop {
+            if let Some(delay) = &mut this.delay {
+                if delay.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+                this.delay = None;
+            }
+
+            if !this.pending.is_empty() {
+                let slice = (this.bytes_per_sec / 10).max(1) as usize;
+                let len = slice.min(this.pending.len());
+                let started = *this.started.get_or_insert_with(tokio::time::Instant::now);
+                let due = started
+                    + Duration::from_secs_f64(
+                        (this.passed + len as u64) as f64 / this.bytes_per_sec as f64,
+                    );
+                if due > tokio::time::Instant::now() {
+                    this.delay = Some(Box::pin(tokio::time::sleep_until(due)));
+                    continue;
+                }
+                this.passed += len as u64;
+                return Poll::Ready(Some(Ok(http_body::Frame::data(this.pending.split_to(len)))));
+            }
+
+            match std::pin::Pin::new(&mut this.inner).poll_frame(cx) {
+                Poll::Ready(Some(Ok(frame))) => match frame.into_data() {
+                    Ok(data) => this.pending = data,
+                    // Trailers aren't counted against the rate
+                    Err(frame) => return Poll::Ready(Some(Ok(frame))),
+                },
+                other => return other,
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.pending.is_empty() && self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> http_body::SizeHint {
+        let mut hint = self.inner.size_hint();
+        let pending = self.pending.len() as u64;
+        hint.set_lower(hint.lower() + pending);
+        if let Some(upper) = hint.upper() {
+            hint.set_upper(upper + pending);
+        }
+        hint
+    }
+}
+
+/// Decodes a text body using the `charset` from its Content-Type, defaulting
+/// to UTF-8. A byte order mark takes precedence, as in browsers.
+fn decode_text(content_type: &str, bytes: &[u8]) -> String {
+    let encoding = content_type
+        .split(';')
+        .skip(1)
+        .find_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            key.trim()
+                .eq_ignore_ascii_case("charset")
+                .then(|| value.trim().trim_matches('"'))
+        })
+        .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(bytes).0.into_owned()
+}
+
+/// A body with its content encoding undone as far as possible.
+#[derive(Debug)]
+pub struct DecodedBody {
+    pub bytes: Vec<u8>,
+    /// Why the body was left partly or wholly encoded, when a coding isn't
+    /// supported
+    pub note: Option<String>,
+}
+
+/// Undoes a `Content-Encoding` header value. Codings are listed in the order
+/// they were applied, so they are removed last to first. An unsupported
+/// coding stops decoding there and leaves the bytes as they are.
+pub fn decode_content_encoding(
+    content_encoding: &str,
+    bytes: Vec<u8>,
+) -> Result<DecodedBody, String> {
+    use std::io::Read;
+
+    let mut data = bytes;
+    for coding in content_encoding
+        .rsplit(',')
+        .map(|c| c.trim().to_ascii_lowercase())
+    {
+        let mut out = Vec::new();
+        match coding.as_str() {
+            "" | "identity" => continue,
+            "gzip" | "x-gzip" => {
+                flate2::read::MultiGzDecoder::new(data.as_slice())
+                    .read_to_end(&mut out)
+                    .map_err(|e| format!("gzip: {}", e))?;
+            }
+            // Meant to be zlib-wrapped, but some servers send raw deflate
+            "deflate" => {
+                if flate2::read::ZlibDecoder::new(data.as_slice())
+                    .read_to_end(&mut out)
+                    .is_err()
+                {
+                    out.clear();
+                    flate2::read::DeflateDecoder::new(data.as_slice())
+                        .read_to_end(&mut out)
+                        .map_err(|e| format!("deflate: {}", e))?;
+                }
+            }
+            "br" => {
+                brotli::Decompressor::new(data.as_slice(), 4096)
+                    .read_to_end(&mut out)
+                    .map_err(|e| format!("br: {}", e))?;
+            }
+            other => {
+                return Ok(DecodedBody {
+                    bytes: data,
+                    note: Some(format!(
+                        "Content encoding '{}' isn't supported; showing the body as received",
+                        other
+                    )),
+                });
+            }
+        }
+        data = out;
+    }
+    Ok(DecodedBody {
+        bytes: data,
+        note: None,
+    })
+}
+
+/// Outcome of [`check_connection`].
+#[derive(Debug)]
+pub struct ConnectionCheck {
+    /// `host:port` that was dialed
+    pub address: String,
+    /// Time to open a TCP connection, or why it failed
+    pub tcp: Result<Duration, String>,
+    /// Status and time of a HEAD request, made only once TCP succeeded. For
+    /// https a response also means the TLS handshake went through.
+    pub head: Option<Result<(String, Duration), String>>,
+    pub tls: bool,
+}
+
+/// Checks whether the host in `url` is reachable without sending the real
+/// request: a bare TCP connect, then a HEAD of the URL.
+pub async fn check_connection(
+    client: &reqwest::Client,
+    url: &str,
+    timeout: Duration,
+) -> Result<ConnectionCheck, String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {}", e))?;
+    let host = parsed.host_str().ok_or("URL has no host")?.to_string();
+    let port = parsed
+        .port_or_known_default()
+        .ok_or("URL has no port and no default for its scheme")?;
+    let address = format!("{}:{}", host.trim_matches(['[', ']']), port);
+
+    let started = std::time::Instant::now();
+    let tcp = match tokio::time::timeout(timeout, tokio::net::TcpStream::connect(&address)).await {
+        Ok(Ok(_)) => Ok(started.elapsed()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("timed out after {} seconds", timeout.as_secs())),
+    };
+
+    let head = if tcp.is_ok() {
+        let started = std::time::Instant::now();
+        Some(
+            match client.head(parsed.clone()).timeout(timeout).send().await {
+                Ok(resp) => Ok((
+                    format!(
+                        "{} {}",
+                        resp.status().as_u16(),
+                        resp.status().canonical_reason().unwrap_or("")
+                    ),
+                    started.elapsed(),
+                )),
+                Err(e) => Err(match std::error::Error::source(&e) {
+                    Some(source) => format!("{}: {}", e, source),
+                    None => e.to_string(),
+                }),
+            },
+        )
+    } else {
+        None
+    };
+
+    Ok(ConnectionCheck {
+        address,
+        tcp,
+        head,
+        tls: parsed.scheme() == "https",
+    })
+}
+
+/// Builds `spec` for `client`. Fails when a custom multipart boundary is
+/// invalid or occurs inside one of the parts.
+fn build_request(
+    client: &reqwest::Client,
+    spec: RequestSpec,
+    headers: reqwest::header::HeaderMap,
+) -> Result<reqwest::RequestBuilder, String> {
+    let request = client.request(spec.method.as_reqwest(), spec.full_url());
+
+    let request = if spec.method.has_body() {
+        match spec.content_type {
+            ContentType::Json | ContentType::Auto => {
+                let mime = spec.raw_body_mime();
+                request.body(spec.body).header("Content-Type", mime)
+            }
+            ContentType::FormUrlEncoded => request
+                .body(urlencode_form(&spec.form_data))
+                .header("Content-Type", "application/x-www-form-urlencoded"),
+            ContentType::FormData => {
+                // Assembled by hand rather than with reqwest::multipart so the
+                // boundary can be chosen and the body previewed byte for byte
+                let boundary = if spec.multipart_boundary.trim().is_empty() {
+                    random_boundary()
+                } else {
+                    let boundary = spec.multipart_boundary.trim().to_string();
+                    validate_boundary(&boundary)?;
+                    boundary
+                };
+                let body = assemble_multipart(&spec.form_data, &boundary, false)?;
+                request.body(body).header(
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={}", boundary),
+                )
+            }
+        }
+    } else {
+        request
+    };
+
+    // Add custom headers
+    Ok(request.headers(headers).timeout(spec.timeout))
+}
+
+/// Serializes the text fields with a key as `application/x-www-form-urlencoded`.
+pub fn urlencode_form(fields: &[FormField]) -> String {
+    let mut url = reqwest::Url::parse("form:").expect("static URL parses");
+    {
+        let mut pairs = url.query_pairs_mut();
+        for field in fields {
+            if !field.key.is_empty() && field.field_type == FormFieldType::Text {
+                pairs.append_pair(&field.key, &field.value);
+            }
+        }
+    }
+    url.query().unwrap_or("").to_string()
+}
+
+/// Renders `spec` roughly as it goes out on the wire: request line with the
+/// normalized URL, the resolved headers, then the body. Multipart file
+/// contents are abbreviated as in [`assemble_multipart`]'s preview mode, and
+/// an empty boundary is shown as `{random-boundary}`.
+pub fn preview_request(spec: &RequestSpec) -> String {
+    let url = match reqwest::Url::parse(&spec.full_url()) {
+        Ok(url) => url,
+        Err(e) => return format!("Invalid URL: {}", e),
+    };
+
+    let body_and_type = preview_body(spec);
+    let headers = preview_headers(spec, &url, body_and_type.as_ref());
+
+    let mut out = format!("{:?} {}\n", spec.method, url);
+    for (name, value) in &headers {
+        out.push_str(&format!(
+            "{}: {}\n",
+            name,
+            String::from_utf8_lossy(value.as_bytes())
+        ));
+    }
+    if let Some((body, _)) = body_and_type {
+        out.push('\n');
+        out.push_str(&String::from_utf8_lossy(&body));
+    }
+    out
+}
+
+/// The body bytes and Content-Type `spec` would send, with multipart file
+/// contents abbreviated as in [`assemble_multipart`]'s preview mode.
+fn preview_body(spec: &RequestSpec) -> Option<(Vec<u8>, String)> {
+    let boundary = if spec.multipart_boundary.trim().is_empty() {
+        "{random-boundary}".to_string()
+    } else {
+        spec.multipart_boundary.trim().to_string()
+    };
+    spec.method.has_body().then(|| match spec.content_type {
+        ContentType::Json | ContentType::Auto => (
+            spec.body.clone().into_bytes(),
+            spec.raw_body_mime().to_string(),
+        ),
+        ContentType::FormUrlEncoded => (
+            urlencode_form(&spec.form_data).into_bytes(),
+            "application/x-www-form-urlencoded".to_string(),
+        ),
+        ContentType::FormData => (
+            assemble_multipart(&spec.form_data, &boundary, true)
+                .unwrap_or_else(|e| format!("<{}>", e).into_bytes()),
+            format!("multipart/form-data; boundary={}", boundary),
+        ),
+    })
+}
+
+/// The headers [`preview_request`] shows for `spec`, or `None` when its URL
+/// doesn't parse.
+pub fn preview_request_headers(spec: &RequestSpec) -> Option<reqwest::header::HeaderMap> {
+    let url = reqwest::Url::parse(&spec.full_url()).ok()?;
+    Some(preview_headers(spec, &url, preview_body(spec).as_ref()))
+}
+
+/// An HTTP message written out as text, such as the `message/http` body a
+/// TRACE response echoes back.
+#[derive(Debug, PartialEq)]
+pub struct HttpMessage {
+    /// Request or status line
+    pub start_line: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// Parses `text` as an HTTP message: a start line, header lines up to the
+/// first blank line, then the body. Returns `None` when the first line is
+/// neither a request line nor a status line.
+pub fn parse_http_message(text: &str) -> Option<HttpMessage> {
+    let text = text.trim_start();
+    let (head, body) = text
+        .split_once("\r\n\r\n")
+        .or_else(|| text.split_once("\n\n"))
+        .unwrap_or((text, ""));
+    let mut lines = head.lines();
+    let start_line = lines.next()?.trim().to_string();
+    let words: Vec<&str> = start_line.split_whitespace().collect();
+    let is_start_line = words.len() >= 2
+        && (words[0].starts_with("HTTP/") || words[words.len() - 1].starts_with("HTTP/"));
+    if !is_start_line {
+        return None;
+    }
+
+    let headers = lines
+        .filter_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect();
+    Some(HttpMessage {
+        start_line,
+        headers,
+        body: body.to_string(),
+    })
+}
+
+/// The headers `spec` would send to `url` along with `body_and_type`.
+fn preview_headers(
+    spec: &RequestSpec,
+    url: &reqwest::Url,
+    body_and_type: Option<&(Vec<u8>, String)>,
+) -> reqwest::header::HeaderMap {
+    // Same precedence as build_request: headers written by hand win
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Some(host) = url.host_str() {
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+        if let Ok(value) = reqwest::header::HeaderValue::from_str(&host) {
+            headers.insert(reqwest::header::HOST, value);
+        }
+    }
+    headers.insert(
+        reqwest::header::ACCEPT,
+        reqwest::header::HeaderValue::from_static("*/*"),
+    );
+    if let Some((body, mime)) = body_and_type {
+        if let Ok(value) = reqwest::header::HeaderValue::from_str(mime) {
+            headers.insert(reqwest::header::CONTENT_TYPE, value);
+        }
+        headers.insert(reqwest::header::CONTENT_LENGTH, body.len().into());
+    }
+    for (name, value) in spec.header_map() {
+        if let Some(name) = name {
+            headers.insert(name, value);
+        }
+    }
+    headers
+}
+
+/// Answers `mock://echo` URLs without touching the network: the response is
+/// a JSON description of the request as it would have been sent. Returns
+/// `None` for every other URL.
+pub fn mock_response(spec: &RequestSpec) -> Option<HttpResponse> {
+    let url = reqwest::Url::parse(&spec.full_url()).ok()?;
+    if url.scheme() != "mock" {
+        return None;
+    }
+    let started = std::time::Instant::now();
+    if url.host_str() != Some("echo") {
+        return Some(HttpResponse {
+            status: "Error".to_string(),
+            body: format!(
+                "Unknown mock host '{}'; only mock://echo is supported",
+                url.host_str().unwrap_or_default()
+            ),
+            ..Default::default()
+        });
+    }
+
+    let body_and_type = preview_body(spec);
+    let headers = preview_headers(spec, &url, body_and_type.as_ref());
+    let body_text = body_and_type
+        .as_ref()
+        .map(|(body, _)| String::from_utf8_lossy(body).into_owned());
+    let echo = serde_json::json!({
+        "method": format!("{:?}", spec.method),
+        "url": url.as_str(),
+        "path": url.path(),
+        "query": url
+            .query_pairs()
+            .map(|(k, v)| (k.into_owned(), serde_json::Value::String(v.into_owned())))
+            .collect::<serde_json::Map<_, _>>(),
+        "headers": headers
+            .iter()
+            .map(|(k, v)| {
+                let value = String::from_utf8_lossy(v.as_bytes()).into_owned();
+                (k.to_string(), serde_json::Value::String(value))
+            })
+            .collect::<serde_json::Map<_, _>>(),
+        "body": body_text,
+        "json": body_text
+            .as_deref()
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(text).ok()),
+    });
+
+    let raw_body = serde_json::to_string(&echo).unwrap_or_default();
+    let mut header_map = reqwest::header::HeaderMap::new();
+    header_map.insert(
+        reqwest::header::CONTENT_TYPE,
+        reqwest::header::HeaderValue::from_static("application/json"),
+    );
+    header_map.insert(
+        "x-crabipie-mock",
+        reqwest::header::HeaderValue::from_static("echo"),
+    );
+    Some(HttpResponse {
+        status: "200 OK".to_string(),
+        headers: format!("{:#?}", header_map),
+        header_map,
+        body: pretty_json(raw_body.clone()),
+        response_size: raw_body.len(),
+        raw_body,
+        content_type: "application/json".to_string(),
+        elapsed: started.elapsed(),
+        request_size: body_and_type.map(|(body, _)| body.len()),
+        http_version: "mock".to_string(),
+        ..Default::default()
+    })
+}
+
+/// Longest boundary RFC 2046 allows
+const MAX_BOUNDARY_LEN: usize = 70;
+
+/// Checks a multipart boundary against RFC 2046: 1 to 70 characters from
+/// letters, digits, space and `'()+_,-./:=?`, not ending in a space.
+pub fn validate_boundary(boundary: &str) -> Result<(), String> {
+    if boundary.is_empty() || boundary.len() > MAX_BOUNDARY_LEN {
+        return Err(format!(
+            "boundary must be 1 to {} characters, not {}",
+            MAX_BOUNDARY_LEN,
+            boundary.chars().count()
+        ));
+    }
+    if let Some(c) = boundary
+        .chars()
+        .find(|&c| !c.is_ascii_alphanumeric() && !"'()+_,-./:=? ".contains(c))
+    {
+        return Err(format!("boundary can't contain '{}'", c));
+    }
+    if boundary.ends_with(' ') {
+        return Err("boundary can't end with a space".to_string());
+    }
+    Ok(())
+}
+
+/// A boundary unlikely to appear in any part.
+pub fn random_boundary() -> String {
+    use std::hash::{BuildHasher, Hasher};
+
+    let random = std::collections::hash_map::RandomState::new()
+        .build_hasher()
+        .finish();
+    format!("----CrabiPieBoundary{:016x}", random)
+}
+
+/// Quotes a form field or file name for a Content-Disposition parameter, the
+/// way browsers do.
+fn disposition_quote(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Builds a `multipart/form-data` body from the fields with a key. Files that
+/// can't be read are left out. With `preview` set, file contents that aren't
+/// short UTF-8 text are replaced by a `<N bytes from path>` placeholder, and
+/// directories by `<zip of N files from path>`, so the result can be shown as
+/// text. Fails when a directory can't be zipped, or when a part contains the
+/// boundary delimiter, which would end that part early.
+pub fn assemble_multipart(
+    fields: &[FormField],
+    boundary: &str,
+    preview: bool,
+) -> Result<Vec<u8>, String> {
+    const PREVIEW_LIMIT: usize = 2048;
+
+    let delimiter = format!("--{}", boundary);
+    let mut body = Vec::new();
+    let mut collision = None;
+    let mut push_part = |name: &str, file: Option<(&str, &str)>, data: &[u8], source: &str| {
+        if collision.is_none()
+            && data
+                .windows(delimiter.len())
+                .any(|w| w == delimiter.as_bytes())
+        {
+            collision = Some(if source.is_empty() {
+                format!("field '{}' contains the boundary", name)
+            } else {
+                format!("{} contains the boundary", source)
+            });
+        }
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        body.extend_from_slice(
+            format!(
+                "Content-Disposition: form-data; name=\"{}\"",
+                disposition_quote(name)
+            )
+            .as_bytes(),
+        );
+        if let Some((filename, mime)) = file {
+            body.extend_from_slice(
+                format!(
+                    "; filename=\"{}\"\r\nContent-Type: {}",
+                    disposition_quote(filename),
+                    mime
+                )
+                .as_bytes(),
+            );
+        }
+        body.extend_from_slice(b"\r\n\r\n");
+        let printable = data.len() <= PREVIEW_LIMIT && std::str::from_utf8(data).is_ok();
+        if preview && file.is_some() && !printable {
+            body.extend_from_slice(format!("<{} bytes from {}>", data.len(), source).as_bytes());
+        } else {
+            body.extend_from_slice(data);
+        }
+        body.extend_from_slice(b"\r\n");
+    };
+
+    for field in fields.iter().filter(|f| !f.key.is_empty()) {
+        match field.field_type {
+            FormFieldType::Text => push_part(&field.key, None, field.value.as_bytes(), ""),
+            FormFieldType::File => {
+                for path in &field.files {
+                    let name = std::path::Path::new(path)
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("file");
+                    let dir = std::path::Path::new(path);
+                    if dir.is_dir() {
+                        let filename = format!("{}.zip", name);
+                        let file = Some((filename.as_str(), "application/zip"));
+                        let zip_error = |e| format!("can't zip {}: {}", path, e);
+                        if preview {
+                            // Zipping can take a while, so the preview only counts
+                            let (count, _) = directory_summary(dir).map_err(zip_error)?;
+                            let placeholder = format!("<zip of {} files from {}>", count, path);
+                            push_part(&field.key, file, placeholder.as_bytes(), path);
+                        } else {
+                            let archive = zip_directory(dir).map_err(zip_error)?;
+                            push_part(&field.key, file, &archive, path);
+                        }
+                    } else if let Ok(file_content) = std::fs::read(path) {
+                        let file = Some((name, "application/octet-stream"));
+                        push_part(&field.key, file, &file_content, path);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(e) = collision {
+        return Err(e);
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    Ok(body)
+}
+
+/// Regular files under `dir`, recursively, as (path relative to `dir`
+/// with `/` separators, full path) pairs sorted by relative path. Symlinks
+/// are skipped, so links pointing outside `dir` or back up into it are
+/// never followed.
+fn directory_files(dir: &std::path::Path) -> std::io::Result<Vec<(String, std::path::PathBuf)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current)? {
+            let entry = entry?;
+            // Unlike `Path::is_dir`, this describes a symlink itself
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file()
+                && let Ok(relative) = path.strip_prefix(dir)
+            {
+                let name = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((name, path));
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Number of files and their total size in bytes under `dir`, as they would
+/// be packed by [`zip_directory`].
+pub fn directory_summary(dir: &std::path::Path) -> std::io::Result<(usize, u64)> {
+    let files = directory_files(dir)?;
+    let total = files
+        .iter()
+        .filter_map(|(_, path)| std::fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum();
+    Ok((files.len(), total))
+}
+
+/// Packs every file under `dir` into an in-memory zip archive, with entry
+/// names relative to `dir`. Form fields pointing at a directory are sent
+/// this way as a single `<dir>.zip` part.
+pub fn zip_directory(dir: &std::path::Path) -> std::io::Result<Vec<u8>> {
+    use std::io::Write;
+
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, path) in directory_files(dir)? {
+        writer
+            .start_file(name, options)
+            .map_err(std::io::Error::other)?;
+        writer.write_all(&std::fs::read(&path)?)?;
+    }
+    let cursor = writer.finish().map_err(std::io::Error::other)?;
+    Ok(cursor.into_inner())
+}
+
+/// Splits the query string off `url`, returning the URL without it and the
+/// decoded parameters, or `None` when there is no query to split.
+pub fn split_query(url: &str) -> Option<(String, Vec<QueryParam>)> {
+    let url = url.trim();
+    let parsed = reqwest::Url::parse(url).ok()?;
+    parsed.query().filter(|q| !q.is_empty())?;
+
+    let params = parsed
+        .query_pairs()
+        .map(|(name, value)| QueryParam {
+            name: name.into_owned(),
+            value: value.into_owned(),
+            ..Default::default()
+        })
+        .collect();
+    // Cut the text rather than re-serializing so the rest stays as typed
+    let start = url.find('?')?;
+    let end = url[start..].find('#').map_or(url.len(), |i| start + i);
+    Some((format!("{}{}", &url[..start], &url[end..]), params))
+}
+
+/// Splits `url` into its base, the scheme and host such as
+/// `https://a.io:8080` or a `{{host}}` placeholder, and the path, query and
+/// fragment after it. The text is cut as typed, so the halves always join
+/// back into `url`.
+pub fn split_base_url(url: &str) -> (&str, &str) {
+    let host_start = url.find("://").map_or(0, |i| i + 3);
+    let end = url[host_start..]
+        .find(['/', '?', '#'])
+        .map_or(url.len(), |i| host_start + i);
+    url.split_at(end)
+}
+
+/// A header line that [`parse_header_lines`] could not use.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkippedHeaderLine {
+    /// 1-based line number in the header text
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+/// Result of [`parse_header_lines`]: the usable headers plus every line that
+/// was dropped for being malformed.
+#[derive(Clone, Debug, Default)]
+pub struct ParsedHeaders {
+    pub headers: reqwest::header::HeaderMap,
+    pub skipped: Vec<SkippedHeaderLine>,
+}
+
+/// Toggles a leading `#` on every line touched by the character range
+/// `start..=end`. If all of those lines are already commented they are
+/// uncommented, otherwise they are all commented.
+pub fn toggle_line_comments(text: &str, start: usize, end: usize) -> String {
+    // Map the character positions to the indices of the lines they fall on
+    let line_of = |char_pos: usize| text.chars().take(char_pos).filter(|c| *c == '\n').count();
+    let (first, last) = (line_of(start), line_of(end));
+
+    let lines: Vec<&str> = text.split('\n').collect();
+    let touched = &lines[first..=last.min(lines.len() - 1)];
+    let all_commented = touched
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .all(|l| l.trim_start().starts_with('#'));
+
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if i < first || i > last {
+                return line.to_string();
+            }
+            if all_commented {
+                let indent = line.len() - line.trim_start().len();
+                let rest = &line[indent..];
+                let rest = rest.strip_prefix('#').unwrap_or(rest);
+                let rest = rest.strip_prefix(' ').unwrap_or(rest);
+                format!("{}{}", &line[..indent], rest)
+            } else if line.trim().is_empty() {
+                line.to_string()
+            } else {
+                format!("# {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses `Key: Value` lines into a header map. Blank lines and `#` comments
+/// are ignored; lines that aren't valid headers are reported in `skipped`.
+/// A repeated key keeps its last value.
+pub fn parse_header_lines(text: &str) -> ParsedHeaders {
+    parse_header_lines_with(text, false)
+}
+
+/// Zero-width characters and BOMs that ride along with pasted text
+pub fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{200b}'..='\u{200d}' | '\u{2060}' | '\u{feff}')
+}
+
+/// Trims whitespace, including Unicode spaces, and invisible characters
+/// from both ends of `text`.
+fn strict_trim(text: &str) -> &str {
+    text.trim_matches(|c: char| c.is_whitespace() || is_invisible(c))
+}
+
+/// Like [`parse_header_lines`]; with `strict` set, zero-width characters and
+/// BOMs are trimmed along with whitespace around names and values. Rejected
+/// lines name the first offending character and its 1-based position in the
+/// line either way.
+pub fn parse_header_lines_with(text: &str, strict: bool) -> ParsedHeaders {
+    let trim = |text| {
+        if strict {
+            strict_trim(text)
+        } else {
+            text.trim()
+        }
+    };
+    let mut parsed = ParsedHeaders::default();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = trim(raw);
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut skip = |reason: String| {
+            parsed.skipped.push(SkippedHeaderLine {
+                line: index + 1,
+                text: raw.to_string(),
+                reason,
+            })
+        };
+
+        let Some((key, value)) = line.split_once(':') else {
+            skip("missing ':'".to_string());
+            continue;
+        };
+        let key = trim(key);
+        let value = trim(value);
+
+        // Both parts borrow from `raw`, so offsets into it give the column
+        let first_invalid = |part: &str, valid: fn(char) -> bool| {
+            let start = part.as_ptr() as usize - raw.as_ptr() as usize;
+            part.char_indices().find(|(_, c)| !valid(*c)).map(|(i, c)| {
+                let position = raw[..start + i].chars().count() + 1;
+                let shown = if c.is_control() || c.is_whitespace() || is_invisible(c) {
+                    format!("U+{:04X}", c as u32)
+                } else {
+                    format!("'{}'", c)
+                };
+                format!("invalid character {} at position {}", shown, position)
+            })
+        };
+
+        match (
+            reqwest::header::HeaderName::from_bytes(key.as_bytes()),
+            reqwest::header::HeaderValue::from_str(value),
+        ) {
+            (Ok(header_name), Ok(header_value)) => {
+                parsed.headers.insert(header_name, header_value);
+            }
+            (Err(_), _) if key.is_empty() => skip("empty header name".to_string()),
+            (Err(_), _) => skip(
+                match first_invalid(key, |c| {
+                    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+                }) {
+                    Some(problem) => format!("{} in header name '{}'", problem, key),
+                    None => format!("invalid header name '{}'", key),
+                },
+            ),
+            (_, Err(_)) => skip(
+                match first_invalid(value, |c| c == '\t' || (' '..='~').contains(&c)) {
+                    Some(problem) => format!("{} in value for '{}'", problem, key),
+                    None => format!("invalid value for '{}'", key),
+                },
+            ),
+        }
+    }
+
+    parsed
+}
+
+/// Pretty-prints `text` if it is JSON, otherwise hands it back unchanged.
+pub fn pretty_json(text: String) -> String {
+    match serde_json::from_str::<serde_json::Value>(&text) {
+        Ok(json) => serde_json::to_string_pretty(&json).unwrap_or(text),
+        Err(_) => text,
+    }
+}
+
+/// Byte offset just past the `>` closing the tag at the start of `text`,
+/// skipping any `>` inside quoted attribute values.
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(i + 1),
+            (None, _) => {}
+        }
+    }
+    None
+}
+
+/// Indents XML one element per line, keeping elements that hold only text
+/// on a single line. Text that doesn't look like XML, or whose markup is
+/// cut off, is returned unchanged.
+pub fn pretty_xml(text: &str) -> String {
+    enum Token<'a> {
+        Open(&'a str),
+        Close(&'a str),
+        Standalone(&'a str),
+        Text(&'a str),
+    }
+
+    let trimmed = text.trim_start_matches('\u{feff}').trim();
+    if !trimmed.starts_with('<') {
+        return text.to_string();
+    }
+
+    let mut tokens = Vec::new();
+    let mut rest = trimmed;
+    while !rest.is_empty() {
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let content = rest[..end].trim();
+            if !content.is_empty() {
+                tokens.push(Token::Text(content));
+            }
+            rest = &rest[end..];
+            continue;
+        }
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|i| i + 3)
+        } else if rest.starts_with("<![CDATA[") {
+            rest.find("]]>").map(|i| i + 3)
+        } else {
+            tag_end(rest)
+        };
+        let Some(end) = end else {
+            return text.to_string();
+        };
+        let tag = &rest[..end];
+        tokens.push(if tag.starts_with("<![CDATA[") {
+            Token::Text(tag)
+        } else if tag.starts_with("</") {
+            Token::Close(tag)
+        } else if tag.starts_with("<?") || tag.starts_with("<!") || tag.ends_with("/>") {
+            Token::Standalone(tag)
+        } else {
+            Token::Open(tag)
+        });
+        rest = &rest[end..];
+    }
+
+    let mut out = String::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i < tokens.len() {
+        let line = match (&tokens[i], tokens.get(i + 1), tokens.get(i + 2)) {
+            (Token::Open(open), Some(Token::Text(content)), Some(Token::Close(close))) => {
+                i += 2;
+                format!("{}{}{}", open, content, close)
+            }
+            (Token::Open(open), Some(Token::Close(close)), _) => {
+                i += 1;
+                format!("{}{}", open, close)
+            }
+            (Token::Open(open), _, _) => {
+                depth += 1;
+                out.push_str(&"  ".repeat(depth - 1));
+                out.push_str(open);
+                out.push('\n');
+                i += 1;
+                continue;
+            }
+            (Token::Close(close), _, _) => {
+                depth = depth.saturating_sub(1);
+                close.to_string()
+            }
+            (Token::Standalone(tag), _, _) => tag.to_string(),
+            (Token::Text(content), _, _) => content.to_string(),
+        };
+        out.push_str(&"  ".repeat(depth));
+        out.push_str(&line);
+        out.push('\n');
+        i += 1;
+    }
+    out.pop();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_gzip_and_deflate() {
+        use flate2::{Compression, write::GzEncoder, write::ZlibEncoder};
+        use std::io::Write;
+
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(b"hello").unwrap();
+        let gz = gz.finish().unwrap();
+        assert_eq!(
+            decode_content_encoding("gzip", gz.clone()).unwrap().bytes,
+            b"hello"
+        );
+
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(&gz).unwrap();
+        let stacked = zlib.finish().unwrap();
+        assert_eq!(
+            decode_content_encoding("gzip, deflate", stacked)
+                .unwrap()
+                .bytes,
+            b"hello"
+        );
+
+        assert_eq!(
+            decode_content_encoding("identity", b"x".to_vec())
+                .unwrap()
+                .bytes,
+            b"x"
+        );
+
+        // Unsupported codings fall back to the bytes as received
+        let zstd = decode_content_encoding("zstd", b"x".to_vec()).unwrap();
+        assert_eq!(zstd.bytes, b"x");
+        assert!(zstd.note.is_some());
+        let partial = decode_content_encoding("zstd, gzip", gz).unwrap();
+        assert_eq!(partial.bytes, b"hello");
+        assert!(partial.note.unwrap().contains("zstd"));
+    }
+
+    #[test]
+    fn detects_raw_body_mime() {
+        assert_eq!(detect_body_mime("  {\"a\": 1}"), "application/json");
+        assert_eq!(detect_body_mime("\u{feff}[1]"), "application/json");
+        assert_eq!(detect_body_mime("<note/>"), "application/xml");
+        assert_eq!(detect_body_mime("plain"), "text/plain");
+
+        let mut spec = RequestSpec::new(HttpMethod::PATCH, "http://x");
+        spec.content_type = ContentType::Auto;
+        spec.body = "{}".to_string();
+        spec.patch_format = PatchFormat::MergePatch;
+        assert_eq!(spec.raw_body_mime(), "application/merge-patch+json");
+        spec.body = "<a/>".to_string();
+        assert_eq!(spec.raw_body_mime(), "application/xml");
+    }
+
+    #[test]
+    fn zips_directory_recursively() {
+        let dir = std::env::temp_dir().join(format!("crabipie_zip_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a.txt"), "hello").unwrap();
+        std::fs::write(dir.join("sub").join("b.txt"), "world!").unwrap();
+        // A link back up must not be followed
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, dir.join("sub").join("up")).unwrap();
+
+        assert_eq!(directory_summary(&dir).unwrap(), (2, 11));
+        let field = FormField {
+            key: "dir".to_string(),
+            field_type: FormFieldType::File,
+            files: vec![dir.display().to_string()],
+            ..Default::default()
+        };
+        let preview = assemble_multipart(&[field], "B", true).unwrap();
+        assert!(
+            String::from_utf8(preview)
+                .unwrap()
+                .contains("<zip of 2 files from ")
+        );
+        let archive = zip_directory(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        let mut names: Vec<_> = archive
+            .file_names()
+            .map(|n| n.unwrap().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["a.txt", "sub/b.txt"]);
+        let mut content = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("sub/b.txt").unwrap(), &mut content)
+            .unwrap();
+        assert_eq!(content, "world!");
+    }
+
+    #[test]
+    fn pretty_prints_xml() {
+        assert_eq!(
+            pretty_xml("<?xml version=\"1.0\"?><a x=\"1\"><b>hi</b><c/><d></d><!-- n --></a>"),
+            "<?xml version=\"1.0\"?>\n<a x=\"1\">\n  <b>hi</b>\n  <c/>\n  <d></d>\n  <!-- n -->\n</a>"
+        );
+        assert_eq!(pretty_xml("not xml"), "not xml");
+        assert_eq!(
+            pretty_xml("<a t=\"x > y\" u='>'><b/></a>"),
+            "<a t=\"x > y\" u='>'>\n  <b/>\n</a>"
+        );
+        assert_eq!(pretty_xml("<a><b"), "<a><b");
+    }
+
+    #[test]
+    fn splits_and_rebuilds_query() {
+        let (base, params) = split_query("https://a.io/x?q=a%20b&n=1#top").unwrap();
+        assert_eq!(base, "https://a.io/x#top");
+        assert_eq!(params.len(), 2);
+        assert_eq!(
+            (params[0].name.as_str(), params[0].value.as_str()),
+            ("q", "a b")
+        );
+        assert!(split_query("https://a.io/x").is_none());
+
+        let mut spec = RequestSpec::new(HttpMethod::GET, base);
+        spec.params = params;
+        spec.params[1].enabled = false;
+        assert_eq!(spec.full_url(), "https://a.io/x?q=a+b#top");
+    }
+
+    #[test]
+    fn splits_base_url_from_path() {
+        assert_eq!(
+            split_base_url("https://a.io:8080/v1/items?x=1"),
+            ("https://a.io:8080", "/v1/items?x=1")
+        );
+        assert_eq!(split_base_url("https://a.io?x=1"), ("https://a.io", "?x=1"));
+        assert_eq!(split_base_url("{{host}}/users"), ("{{host}}", "/users"));
+        assert_eq!(split_base_url("/users"), ("", "/users"));
+        assert_eq!(split_base_url("https://a.io"), ("https://a.io", ""));
+    }
+
+    #[test]
+    fn parses_echoed_http_message() {
+        let message =
+            parse_http_message("TRACE /x HTTP/1.1\r\nHost: a.io\r\nVia: 1.1 edge\r\n\r\n").unwrap();
+        assert_eq!(message.start_line, "TRACE /x HTTP/1.1");
+        assert_eq!(
+            message.headers,
+            vec![
+                ("Host".to_string(), "a.io".to_string()),
+                ("Via".to_string(), "1.1 edge".to_string())
+            ]
+        );
+        assert!(message.body.is_empty());
+        assert!(parse_http_message(r#"{"a": 1}"#).is_none());
+    }
+
+    #[test]
+    fn builds_without_sending() {
+        let client = reqwest::Client::new();
+        let spec = RequestSpec::new(HttpMethod::POST, "https://a.io/x").json_body(r#"{"a":1}"#);
+        assert_eq!(build_without_sending(&client, spec), Ok(Some(7)));
+        let spec = RequestSpec::new(HttpMethod::GET, "not a url");
+        assert!(build_without_sending(&client, spec).is_err());
+    }
+
+    // The clock is paused and jumps ahead to each pending timer, so this
+    // checks the schedule without waiting it out
+    #[tokio::test(start_paused = true)]
+    async fn throttles_body_to_rate() {
+        use http_body_util::BodyExt;
+
+        let body = http_body_util::Full::new(bytes::Bytes::from(vec![7u8; 3000]));
+        let started = tokio::time::Instant::now();
+        let collected = Throttled::new(body, 10_000).collect().await.unwrap();
+        assert_eq!(collected.to_bytes().len(), 3000);
+        // Three slices of 1000 bytes, each due a tenth of a second apart
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(300), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(310), "{:?}", elapsed);
+    }
+
+    #[test]
+    fn echoes_mock_requests() {
+        let spec = RequestSpec::new(HttpMethod::POST, "mock://echo/items?page=2")
+            .header("X-Trace", "1")
+            .json_body(r#"{"a": 1}"#);
+        let response = mock_response(&spec).unwrap();
+        assert_eq!(response.status, "200 OK");
+        let echo: serde_json::Value = serde_json::from_str(&response.raw_body).unwrap();
+        assert_eq!(echo["method"], "POST");
+        assert_eq!(echo["path"], "/items");
+        assert_eq!(echo["query"]["page"], "2");
+        assert_eq!(echo["headers"]["x-trace"], "1");
+        assert_eq!(echo["headers"]["content-type"], "application/json");
+        assert_eq!(echo["json"]["a"], 1);
+
+        assert!(mock_response(&RequestSpec::from("https://example.com")).is_none());
+        assert_eq!(
+            mock_response(&RequestSpec::from("mock://other"))
+                .unwrap()
+                .status,
+            "Error"
+        );
+    }
+
+    #[test]
+    fn decodes_text_with_charset() {
+        assert_eq!(decode_text("text/plain", "héllo".as_bytes()), "héllo");
+        assert_eq!(
+            decode_text("text/plain; charset=\"ISO-8859-1\"", &[0x68, 0xe9]),
+            "hé"
+        );
+    }
+
+    #[test]
+    fn assembles_multipart_text_fields() {
+        let fields = vec![
+            FormField {
+                key: "a".to_string(),
+                value: "1".to_string(),
+                ..Default::default()
+            },
+            FormField::default(),
+            FormField {
+                key: "say \"hi\"".to_string(),
+                value: "x\r\ny".to_string(),
+                ..Default::default()
+            },
+        ];
+        let body = assemble_multipart(&fields, "B", false).unwrap();
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "--B\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n\
+             --B\r\nContent-Disposition: form-data; name=\"say %22hi%22\"\r\n\r\nx\r\ny\r\n\
+             --B--\r\n"
+        );
+
+        let clash = vec![FormField {
+            key: "a".to_string(),
+            value: "x--B".to_string(),
+            ..Default::default()
+        }];
+        assert!(assemble_multipart(&clash, "B", false).is_err());
+    }
+
+    #[test]
+    fn validates_multipart_boundary() {
+        assert!(validate_boundary("----WebKitFormBoundary7MA4YWxk").is_ok());
+        assert!(validate_boundary("a b'()+_,-./:=?").is_ok());
+        assert!(validate_boundary("").is_err());
+        assert!(validate_boundary(&"x".repeat(71)).is_err());
+        assert!(validate_boundary("semi;colon").is_err());
+        assert!(validate_boundary("trailing ").is_err());
+    }
+
+    #[test]
+    fn previews_urlencoded_request() {
+        let spec = RequestSpec::new(HttpMethod::POST, "http://example.com:8080/a b")
+            .form(
+                vec![FormField {
+                    key: "q".to_string(),
+                    value: "a&b c".to_string(),
+                    ..Default::default()
+                }],
+                false,
+            )
+            .header("X-Trace", "1");
+        let preview = preview_request(&spec);
+        assert!(preview.starts_with("POST http://example.com:8080/a%20b\n"));
+        assert!(preview.contains("host: example.com:8080\n"));
+        assert!(preview.contains("content-type: application/x-www-form-urlencoded\n"));
+        assert!(preview.contains("content-length: 9\n"));
+        assert!(preview.contains("x-trace: 1\n"));
+        assert!(preview.ends_with("\n\nq=a%26b+c"));
+    }
+
+    #[test]
+    fn parses_simple_headers() {
+        let parsed = parse_header_lines("Accept: application/json\nX-Id:  42 ");
+        assert_eq!(parsed.headers["accept"], "application/json");
+        assert_eq!(parsed.headers["x-id"], "42");
+        assert!(parsed.skipped.is_empty());
+    }
+
+    #[test]
+    fn ignores_comments_and_blank_lines() {
+        let parsed = parse_header_lines("# X-Off: 1\n\n   \n  # indented\nX-On: 1");
+        assert_eq!(parsed.headers.len(), 1);
+        assert!(parsed.headers.contains_key("x-on"));
+        assert!(parsed.skipped.is_empty());
+    }
+
+    #[test]
+    fn reports_missing_colon() {
+        let parsed = parse_header_lines("Accept: */*\nnot a header");
+        assert_eq!(parsed.headers.len(), 1);
+        assert_eq!(
+            parsed.skipped,
+            vec![SkippedHeaderLine {
+                line: 2,
+                text: "not a header".to_string(),
+                reason: "missing ':'".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn duplicate_keys_keep_last_value() {
+        let parsed = parse_header_lines("X-Dup: first\nx-dup: second");
+        assert_eq!(parsed.headers.get_all("x-dup").iter().count(), 1);
+        assert_eq!(parsed.headers["x-dup"], "second");
+    }
+
+    #[test]
+    fn reports_invalid_name_and_value() {
+        let parsed = parse_header_lines("Bad Name: 1\nX-Ctl: a\u{7f}b\nX-Ok: 1");
+        assert_eq!(parsed.headers.len(), 1);
+        assert_eq!(parsed.skipped.len(), 2);
+        assert_eq!(parsed.skipped[0].line, 1);
+        assert!(parsed.skipped[0].reason.contains("header name"));
+        assert_eq!(parsed.skipped[1].line, 2);
+        assert!(parsed.skipped[1].reason.contains("value"));
+    }
+
+    #[test]
+    fn reports_position_of_invalid_character() {
+        let parsed = parse_header_lines("X-Token: abc\u{7f}def\nX (a): 1");
+        assert_eq!(
+            parsed.skipped[0].reason,
+            "invalid character U+007F at position 13 in value for 'X-Token'"
+        );
+        assert_eq!(
+            parsed.skipped[1].reason,
+            "invalid character U+0020 at position 2 in header name 'X (a)'"
+        );
+    }
+
+    #[test]
+    fn toggles_comments_on_touched_lines() {
+        let text = "A: 1\nB: 2\nC: 3";
+        // Selection from inside line 1 to inside line 2
+        assert_eq!(toggle_line_comments(text, 2, 7), "# A: 1\n# B: 2\nC: 3");
+        // An empty selection toggles the cursor's line
+        assert_eq!(toggle_line_comments(text, 6, 6), "A: 1\n# B: 2\nC: 3");
+        assert_eq!(toggle_line_comments("# A: 1", 0, 0), "A: 1");
+    }
+
+    #[test]
+    fn comments_mixed_lines_and_keeps_indentation() {
+        // Not all commented: everything gets commented, blank lines are left alone
+        assert_eq!(
+            toggle_line_comments("# A: 1\n\nB: 2", 0, 10),
+            "# # A: 1\n\n# B: 2"
+        );
+        // All commented: the marker is removed after the indentation
+        assert_eq!(
+            toggle_line_comments("  # A: 1\n\t#B: 2", 0, 12),
+            "  A: 1\n\tB: 2"
+        );
+    }
+
+    #[test]
+    fn strict_mode_trims_invisible_characters() {
+        let text = "\u{feff}Authorization: Bearer abc\u{200b}";
+        assert_eq!(parse_header_lines(text).skipped.len(), 1);
+        let parsed = parse_header_lines_with(text, true);
+        assert!(parsed.skipped.is_empty());
+        assert_eq!(parsed.headers["authorization"], "Bearer abc");
+
+        let mut spec = RequestSpec::new(HttpMethod::GET, "http://x").bearer("abc\u{200b}\n");
+        assert!(spec.header_map().get("authorization").is_none());
+        spec.strict_headers = true;
+        assert_eq!(spec.header_map()["authorization"], "Bearer abc");
+    }
+}
@@ -0,0 +1,399 @@
+//! Conversion between [`RequestSpec`]/[`HttpResponse`] and HAR 1.2, the
+//! archive format browser devtools export network traffic in.
+
+use crate::client::{ContentType, FormField, HttpMethod, HttpResponse, RequestSpec};
+use serde_json::{Value, json};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A request read from a HAR file.
+#[derive(Clone, Debug)]
+pub struct HarEntry {
+    /// Method as written in the file, which may be one CrabiPie can't send
+    pub method: String,
+    pub url: String,
+    /// Recorded response status, if the entry has one
+    pub status: Option<u16>,
+    /// `None` when the method isn't supported
+    pub spec: Option<RequestSpec>,
+    /// Parts of the entry that couldn't be carried over
+    pub warnings: Vec<String>,
+}
+
+/// Headers that are derived from the connection or the body and would be
+/// wrong if replayed verbatim.
+const SKIPPED_REQUEST_HEADERS: &[&str] = &["host", "content-length", "connection"];
+
+/// Reads every entry of a HAR document.
+pub fn import(text: &str) -> Result<Vec<HarEntry>, String> {
+    let har: Value = serde_json::from_str(text).map_err(|e| format!("Not valid JSON: {}", e))?;
+    let entries = har
+        .pointer("/log/entries")
+        .and_then(Value::as_array)
+        .ok_or("No log.entries array; is this a HAR file?")?;
+
+    Ok(entries.iter().map(import_entry).collect())
+}
+
+fn import_entry(entry: &Value) -> HarEntry {
+    let request = &entry["request"];
+    let method = request["method"].as_str().unwrap_or("GET").to_string();
+    let url = request["url"].as_str().unwrap_or_default().to_string();
+    let status = entry
+        .pointer("/response/status")
+        .and_then(Value::as_u64)
+        .filter(|&s| s > 0)
+        .map(|s| s as u16);
+    let mut warnings = Vec::new();
+
+    let Ok(http_method) = method.parse::<HttpMethod>() else {
+        warnings.push(format!("{} requests aren't supported", method));
+        return HarEntry {
+            method,
+            url,
+            status,
+            spec: None,
+            warnings,
+        };
+    };
+
+    let mut spec = RequestSpec::new(http_method, url.clone());
+    let post_data = &request["postData"];
+    let mime = post_data["mimeType"].as_str().unwrap_or_default();
+    let form_body = mime.starts_with("application/x-www-form-urlencoded")
+        || mime.starts_with("multipart/form-data");
+
+    for header in request["headers"].as_array().into_iter().flatten() {
+        let name = header["name"].as_str().unwrap_or_default();
+        let value = header["value"].as_str().unwrap_or_default();
+        let lower = name.to_ascii_lowercase();
+        // HTTP/2 pseudo-headers such as :authority come from the URL
+        if name.is_empty()
+            || name.starts_with(':')
+            || SKIPPED_REQUEST_HEADERS.contains(&lower.as_str())
+            || (form_body && lower == "content-type")
+        {
+            continue;
+        }
+        spec = spec.header(name, value);
+    }
+
+    if !post_data.is_null() {
+        if form_body {
+            let multipart = mime.starts_with("multipart/form-data");
+            let mut fields = Vec::new();
+            for param in post_data["params"].as_array().into_iter().flatten() {
+                let key = param["name"].as_str().unwrap_or_default();
+                if param["fileName"].is_string() {
+                    warnings.push(format!("File field '{}' was left out", key));
+                    continue;
+                }
+                fields.push(FormField {
+                    key: key.to_string(),
+                    value: param["value"].as_str().unwrap_or_default().to_string(),
+                    ..Default::default()
+                });
+            }
+            if fields.is_empty()
+                && !multipart
+                && let Some(text) = post_data["text"].as_str()
+                && let Ok(parsed) = reqwest::Url::parse(&format!("form:?{}", text))
+            {
+                fields = parsed
+                    .query_pairs()
+                    .map(|(key, value)| FormField {
+                        key: key.into_owned(),
+                        value: value.into_owned(),
+                        ..Default::default()
+                    })
+                    .collect();
+            }
+            spec = spec.form(fields, multipart);
+        } else {
+            spec = spec.json_body(post_data["text"].as_str().unwrap_or_default());
+            if !mime.is_empty() && !mime.contains("json") {
+                warnings.push(format!("Body was {}, loaded as raw text", mime));
+            }
+        }
+    }
+
+    HarEntry {
+        method,
+        url,
+        status,
+        spec: Some(spec),
+        warnings,
+    }
+}
+
+fn name_values<'a>(pairs: impl Iterator<Item = (&'a str, String)>) -> Value {
+    pairs
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+/// The Content-Type sent with `request`'s body. A multipart body gets a fresh
+/// random boundary per send, which isn't kept, so only a custom one is shown.
+fn body_content_type(request: &RequestSpec) -> Option<String> {
+    if !request.method.has_body() {
+        return None;
+    }
+    Some(match request.content_type {
+        ContentType::Json | ContentType::Auto => request.raw_body_mime().to_string(),
+        ContentType::FormUrlEncoded => "application/x-www-form-urlencoded".to_string(),
+        ContentType::FormData => match request.multipart_boundary.trim() {
+            "" => "multipart/form-data".to_string(),
+            boundary => format!("multipart/form-data; boundary={}", boundary),
+        },
+    })
+}
+
+/// Builds a single-entry HAR document for `request` and the `response` it got.
+/// `started` is when the request was sent.
+pub fn export(request: &RequestSpec, response: &HttpResponse, started: SystemTime) -> Value {
+    let mut headers = request.header_map();
+    // `build_request` adds the body's Content-Type unless a header sets one
+    if let Some(mime) = body_content_type(request)
+        && !headers.contains_key(reqwest::header::CONTENT_TYPE)
+        && let Ok(value) = reqwest::header::HeaderValue::from_str(&mime)
+    {
+        headers.insert(reqwest::header::CONTENT_TYPE, value);
+    }
+    let header_pairs = || {
+        headers.iter().map(|(n, v)| {
+            (
+                n.as_str(),
+                String::from_utf8_lossy(v.as_bytes()).into_owned(),
+            )
+        })
+    };
+    let url = request.full_url();
+    let query = reqwest::Url::parse(&url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(k, v)| json!({ "name": k, "value": v }))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let post_data = request
+        .method
+        .has_body()
+        .then(|| match request.content_type {
+            ContentType::Json | ContentType::Auto => json!({
+                "mimeType": request.raw_body_mime(),
+                "text": request.body,
+            }),
+            ContentType::FormUrlEncoded | ContentType::FormData => {
+                let params: Vec<Value> = request
+                    .form_data
+                    .iter()
+                    .filter(|f| !f.key.is_empty())
+                    .flat_map(|f| {
+                        if f.files.is_empty() {
+                            vec![json!({ "name": f.key, "value": f.value })]
+                        } else {
+                            f.files
+                                .iter()
+                                .map(|path| {
+                                    let file_name = std::path::Path::new(path)
+                                        .file_name()
+                                        .map(|n| n.to_string_lossy().into_owned())
+                                        .unwrap_or_default();
+                                    json!({ "name": f.key, "fileName": file_name })
+                                })
+                                .collect()
+                        }
+                    })
+                    .collect();
+                let mime = if request.content_type == ContentType::FormData {
+                    "multipart/form-data"
+                } else {
+                    "application/x-www-form-urlencoded"
+                };
+                json!({ "mimeType": mime, "params": params, "text": "" })
+            }
+        });
+
+    let (status, status_text) = match response.status.split_once(' ') {
+        Some((code, text)) => (code.parse::<u16>().unwrap_or(0), text.trim()),
+        None => (0, response.status.as_str()),
+    };
+    let content = if response.is_binary {
+        use base64::Engine;
+        json!({
+            "size": response.bytes.len(),
+            "mimeType": response.content_type,
+            "text": base64::engine::general_purpose::STANDARD.encode(&response.bytes),
+            "encoding": "base64",
+        })
+    } else {
+        // The body as received, not the pretty printed one shown in the app
+        json!({
+            "size": response.raw_body.len(),
+            "mimeType": response.content_type,
+            "text": response.raw_body,
+        })
+    };
+    let http_version = if response.http_version.is_empty() {
+        "HTTP/1.1".to_string()
+    } else {
+        response.http_version.clone()
+    };
+    let elapsed_ms = response.elapsed.as_secs_f64() * 1000.0;
+
+    let mut request_json = json!({
+        "method": format!("{:?}", request.method),
+        "url": url,
+        "httpVersion": http_version,
+        "cookies": [],
+        "headers": name_values(header_pairs()),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": response.request_size.map(|s| s as i64).unwrap_or(-1),
+    });
+    if let Some(post_data) = post_data {
+        request_json["postData"] = post_data;
+    }
+
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "CrabiPie", "version": env!("CARGO_PKG_VERSION") },
+            "entries": [{
+                "startedDateTime": iso8601(started),
+                "time": elapsed_ms,
+                "request": request_json,
+                "response": {
+                    "status": status,
+                    "statusText": status_text,
+                    "httpVersion": http_version,
+                    "cookies": [],
+                    "headers": name_values(response.header_map.iter().map(|(n, v)| {
+                        (n.as_str(), String::from_utf8_lossy(v.as_bytes()).into_owned())
+                    })),
+                    "content": content,
+                    "redirectURL": response.location,
+                    "headersSize": -1,
+                    "bodySize": response.response_size,
+                },
+                "cache": {},
+                "timings": { "send": 0, "wait": elapsed_ms, "receive": 0 },
+            }],
+        }
+    })
+}
+
+/// Formats a time as UTC `YYYY-MM-DDTHH:MM:SS.mmmZ`.
+fn iso8601(time: SystemTime) -> String {
+    let millis = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default();
+    let days = millis.div_euclid(86_400_000);
+    let day_millis = millis.rem_euclid(86_400_000);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        day_millis / 3_600_000,
+        day_millis / 60_000 % 60,
+        day_millis / 1000 % 60,
+        day_millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn formats_iso8601() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_251_200_123);
+        assert_eq!(iso8601(time), "2024-03-01T00:00:00.123Z");
+        assert_eq!(iso8601(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn imports_requests() {
+        let har = r#"{"log": {"entries": [
+            {"request": {"method": "POST", "url": "https://example.com/a",
+                "headers": [{"name": ":authority", "value": "example.com"},
+                            {"name": "Content-Type", "value": "application/json"},
+                            {"name": "Content-Length", "value": "2"},
+                            {"name": "X-Id", "value": "7"}],
+                "postData": {"mimeType": "application/json", "text": "{}"}},
+             "response": {"status": 201}},
+            {"request": {"method": "POST", "url": "https://example.com/f", "headers": [],
+                "postData": {"mimeType": "application/x-www-form-urlencoded", "text": "a=1&b=x+y"}},
+             "response": {"status": 0}},
+            {"request": {"method": "OPTIONS", "url": "https://example.com/", "headers": []}}
+        ]}}"#;
+        let entries = import(har).unwrap();
+        assert_eq!(entries.len(), 3);
+
+        let spec = entries[0].spec.as_ref().unwrap();
+        assert_eq!(entries[0].status, Some(201));
+        assert_eq!(spec.body, "{}");
+        assert_eq!(spec.headers, "Content-Type: application/json\nX-Id: 7");
+
+        let form = entries[1].spec.as_ref().unwrap();
+        assert_eq!(entries[1].status, None);
+        assert_eq!(form.content_type, ContentType::FormUrlEncoded);
+        assert_eq!(form.form_data[1].value, "x y");
+
+        assert!(entries[2].spec.is_none());
+        assert!(!entries[2].warnings.is_empty());
+    }
+
+    #[test]
+    fn exported_request_imports_back() {
+        let spec = RequestSpec::new(HttpMethod::PUT, "https://example.com/items/1?v=2")
+            .header("X-Trace", "1")
+            .json_body(r#"{"a":1}"#);
+        let response = HttpResponse {
+            status: "200 OK".to_string(),
+            body: "{\n  \"ok\": true\n}".to_string(),
+            raw_body: "{\"ok\":true}".to_string(),
+            content_type: "application/json".to_string(),
+            ..Default::default()
+        };
+        let har = export(&spec, &response, UNIX_EPOCH);
+        assert_eq!(har["log"]["entries"][0]["response"]["status"], 200);
+        assert_eq!(
+            har["log"]["entries"][0]["response"]["content"]["text"],
+            "{\"ok\":true}"
+        );
+        assert_eq!(
+            har["log"]["entries"][0]["request"]["queryString"][0]["value"],
+            "2"
+        );
+        let request_headers = har["log"]["entries"][0]["request"]["headers"]
+            .as_array()
+            .unwrap();
+        assert!(
+            request_headers
+                .contains(&json!({ "name": "content-type", "value": "application/json" }))
+        );
+
+        let entries = import(&har.to_string()).unwrap();
+        let imported = entries[0].spec.as_ref().unwrap();
+        assert_eq!(imported.method, HttpMethod::PUT);
+        assert_eq!(imported.url, spec.url);
+        assert_eq!(imported.body, spec.body);
+        assert!(imported.headers.contains("x-trace: 1"));
+    }
+}
@@ -1,9 +1,18 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+use clap::Parser;
+use crabipie::client::{
+    self, AuthType, ConnectionCheck, ContentType, CookieRow, FormField, FormFieldType, HttpMethod,
+    HttpResponse, PatchFormat, QueryParam, RequestSpec, parse_header_lines_with, pretty_json,
+    pretty_xml,
+};
+use crabipie::har::{self, HarEntry};
+use crabipie::security::{self, HeaderCheck, Verdict};
 use eframe::egui;
 use egui::IconData;
 use egui_extras::{Size, StripBuilder};
+use notify::{RecursiveMode, Watcher};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, VecDeque},
     sync::{
         atomic::Ordering,
         mpsc::{self, Receiver, Sender},
@@ -13,38 +22,338 @@ use std::{
 
 // const CRABIPIE_ICON_BASE64: &str = "some base64 string here";
 
+const BACKGROUND_FORMAT_THRESHOLD: usize = 256 * 1024;
+/// How much of a collapsed response body is shown
+const COLLAPSED_BODY_PREVIEW: usize = 64 * 1024;
+
 #[derive(PartialEq)]
 enum RequestTab {
     Body,
+    Params,
     Headers,
     Auth,
+    Cookies,
+    Schema,
+    Preview,
 }
 
-#[derive(PartialEq, Clone)]
-enum ContentType {
-    Json,
-    FormData,
-    FormUrlEncoded,
+#[derive(Clone)]
+struct HostProfile {
+    host: String,
+    headers: String,
+    auth_type: AuthType,
+    bearer_token: String,
 }
 
-#[derive(Clone, PartialEq)]
-enum FormFieldType {
-    Text,
-    File,
+/// A named set of `{{variable}}` values substituted into requests when active.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct Environment {
+    name: String,
+    /// `name = value` lines; blank lines and `#` comments are ignored
+    variables: String,
 }
 
-#[derive(Clone)]
-struct FormField {
-    key: String,
-    value: String,
-    files: Vec<String>,
-    field_type: FormFieldType,
+/// State of the quick environment switcher overlay.
+#[derive(Default)]
+struct EnvSwitcher {
+    filter: String,
+    selected: usize,
 }
 
-#[derive(PartialEq, Clone)]
-enum AuthType {
-    None,
-    Bearer,
+/// App-wide preferences, edited in the settings window and saved between
+/// sessions. Fields missing from saved settings keep their defaults.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct Settings {
+    show_metrics_bar: bool,
+    format_on_paste: bool,
+    /// Stay on the current response tab instead of jumping to Body on each response
+    keep_response_tab: bool,
+    /// Selecting a node in the JSON tree moves the Body cursor to its line,
+    /// and moving the cursor selects the node
+    sync_json_views: bool,
+    /// Show only the start of text bodies over this many KB until asked for
+    /// the rest; `None` always renders everything
+    collapse_body_over_kb: Option<usize>,
+    /// Prettify bodies over `BACKGROUND_FORMAT_THRESHOLD` on a worker thread
+    background_formatting: bool,
+    /// `Accept-Encoding` added to requests that don't set one; empty sends none
+    accept_encoding: String,
+    /// `Accept-Language` added to requests that don't set one; empty sends none
+    accept_language: String,
+    /// How many repeat-run requests may be in flight at once; 1 is sequential
+    max_concurrent_requests: usize,
+    /// Strip pasted zero-width characters and BOMs from headers and the token
+    strict_header_trim: bool,
+    /// Move the query string of a URL pasted into the URL box into Params
+    split_pasted_query: bool,
+    /// Edit the URL as a base (scheme and host) and a path
+    split_url: bool,
+    /// Method and body type for new requests and tabs
+    default_method: HttpMethod,
+    default_content_type: ContentType,
+    /// `None` keeps reqwest's default of no limit
+    pool_max_idle_per_host: Option<usize>,
+    /// `None` keeps idle connections forever; reqwest defaults to 90 seconds
+    pool_idle_timeout_secs: Option<u64>,
+    /// Pretty (true) or raw (false) response view per body format, as named by
+    /// `body_format`
+    pretty_by_format: BTreeMap<String, bool>,
+    /// Remapped shortcuts; actions missing here use their default
+    shortcuts: BTreeMap<ShortcutAction, egui::KeyboardShortcut>,
+}
+
+impl Settings {
+    fn shortcut(&self, action: ShortcutAction) -> egui::KeyboardShortcut {
+        self.shortcuts
+            .get(&action)
+            .copied()
+            .unwrap_or_else(|| action.default_shortcut())
+    }
+
+    /// Names of the other actions bound to the same keys as `action`.
+    fn shortcut_conflicts(&self, action: ShortcutAction) -> Vec<&'static str> {
+        let shortcut = self.shortcut(action);
+        let same = |modifiers: egui::Modifiers, key: egui::Key| {
+            key == shortcut.logical_key && modifiers.matches_exact(shortcut.modifiers)
+        };
+        let remappable = ShortcutAction::ALL
+            .into_iter()
+            .filter(|&other| other != action)
+            .filter(|&other| {
+                let other_shortcut = self.shortcut(other);
+                same(other_shortcut.modifiers, other_shortcut.logical_key)
+            })
+            .map(ShortcutAction::label);
+        let fixed = FIXED_SHORTCUTS
+            .into_iter()
+            .filter(|(_, modifiers, key)| same(*modifiers, *key))
+            .map(|(name, _, _)| name);
+        remappable.chain(fixed).collect()
+    }
+
+    /// Applies the default method and body type to a new request.
+    fn with_request_defaults(&self, mut spec: RequestSpec) -> RequestSpec {
+        spec.method = self.default_method.clone();
+        spec.content_type = self.default_content_type.clone();
+        spec
+    }
+}
+
+/// A remappable keyboard shortcut.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ShortcutAction {
+    Send,
+    FocusUrl,
+    NextRequestTab,
+    PreviousRequestTab,
+    ToggleLayout,
+    FormatBody,
+    Find,
+    Replace,
+    EnvironmentSwitcher,
+}
+
+impl ShortcutAction {
+    const ALL: [ShortcutAction; 9] = [
+        ShortcutAction::Send,
+        ShortcutAction::FocusUrl,
+        ShortcutAction::NextRequestTab,
+        ShortcutAction::PreviousRequestTab,
+        ShortcutAction::ToggleLayout,
+        ShortcutAction::FormatBody,
+        ShortcutAction::Find,
+        ShortcutAction::Replace,
+        ShortcutAction::EnvironmentSwitcher,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            ShortcutAction::Send => "Send request",
+            ShortcutAction::FocusUrl => "Focus URL",
+            ShortcutAction::NextRequestTab => "Next request tab",
+            ShortcutAction::PreviousRequestTab => "Previous request tab",
+            ShortcutAction::ToggleLayout => "Toggle layout",
+            ShortcutAction::FormatBody => "Prettify body",
+            ShortcutAction::Find => "Find",
+            ShortcutAction::Replace => "Find and replace",
+            ShortcutAction::EnvironmentSwitcher => "Switch environment",
+        }
+    }
+
+    fn default_shortcut(self) -> egui::KeyboardShortcut {
+        use egui::{Key, Modifiers};
+        let ctrl_shift = Modifiers::CTRL | Modifiers::SHIFT;
+        let (modifiers, key) = match self {
+            ShortcutAction::Send => (Modifiers::CTRL, Key::Enter),
+            ShortcutAction::FocusUrl => (Modifiers::CTRL, Key::L),
+            ShortcutAction::NextRequestTab => (Modifiers::CTRL, Key::PageDown),
+            ShortcutAction::PreviousRequestTab => (Modifiers::CTRL, Key::PageUp),
+            ShortcutAction::ToggleLayout => (ctrl_shift, Key::L),
+            ShortcutAction::FormatBody => (ctrl_shift, Key::F),
+            ShortcutAction::Find => (Modifiers::CTRL, Key::F),
+            ShortcutAction::Replace => (Modifiers::CTRL, Key::H),
+            ShortcutAction::EnvironmentSwitcher => (Modifiers::CTRL, Key::E),
+        };
+        egui::KeyboardShortcut::new(modifiers, key)
+    }
+}
+
+/// Shortcuts that can't be remapped, listed so conflicts with them are flagged
+const FIXED_SHORTCUTS: [(&str, egui::Modifiers, egui::Key); 4] = [
+    ("Find next", egui::Modifiers::NONE, egui::Key::F3),
+    ("Find previous", egui::Modifiers::SHIFT, egui::Key::F3),
+    ("Close find", egui::Modifiers::NONE, egui::Key::Escape),
+    (
+        "Comment header lines",
+        egui::Modifiers::CTRL,
+        egui::Key::Slash,
+    ),
+];
+
+/// Whether `shortcut` was pressed this frame, with exactly its modifiers.
+fn shortcut_pressed(input: &egui::InputState, shortcut: &egui::KeyboardShortcut) -> bool {
+    input.key_pressed(shortcut.logical_key) && input.modifiers.matches_exact(shortcut.modifiers)
+}
+
+/// Renders a shortcut as `Ctrl+Shift+F`, the form it is saved in.
+fn format_shortcut(shortcut: &egui::KeyboardShortcut) -> String {
+    let modifiers = shortcut.modifiers;
+    let mut parts = Vec::new();
+    if modifiers.ctrl || modifiers.command {
+        parts.push("Ctrl");
+    }
+    if modifiers.alt {
+        parts.push("Alt");
+    }
+    if modifiers.shift {
+        parts.push("Shift");
+    }
+    parts.push(shortcut.logical_key.name());
+    parts.join("+")
+}
+
+/// Storage key for [`Settings`]
+const SETTINGS_KEY: &str = "settings";
+
+/// Form fields added together from the "Common fields" menu
+const FORM_FIELD_PRESETS: [(&str, &[(&str, FormFieldType)]); 4] = [
+    (
+        "Login",
+        &[
+            ("username", FormFieldType::Text),
+            ("password", FormFieldType::Text),
+        ],
+    ),
+    (
+        "Contact",
+        &[
+            ("name", FormFieldType::Text),
+            ("email", FormFieldType::Text),
+            ("message", FormFieldType::Text),
+        ],
+    ),
+    (
+        "Search",
+        &[
+            ("q", FormFieldType::Text),
+            ("page", FormFieldType::Text),
+            ("limit", FormFieldType::Text),
+        ],
+    ),
+    (
+        "File upload",
+        &[
+            ("file", FormFieldType::File),
+            ("description", FormFieldType::Text),
+        ],
+    ),
+];
+
+/// Example bodies offered for an empty editor, as (menu label, body). PATCH
+/// examples follow the selected patch format.
+fn body_snippets(
+    method: &HttpMethod,
+    patch_format: PatchFormat,
+) -> Vec<(&'static str, &'static str)> {
+    let mut snippets = vec![("Empty object", "{\n  \n}"), ("Empty array", "[\n  \n]")];
+    match (method, patch_format) {
+        (HttpMethod::PATCH, PatchFormat::JsonPatch) => snippets.push((
+            "JSON Patch operations",
+            r#"[
+  { "op": "replace", "path": "/name", "value": "new name" },
+  { "op": "remove", "path": "/obsolete" }
+]"#,
+        )),
+        (HttpMethod::PATCH, _) => {
+            snippets.push(("Partial update", "{\n  \"name\": \"new name\"\n}"))
+        }
+        _ => {
+            snippets.push((
+                "Login",
+                "{\n  \"username\": \"user\",\n  \"password\": \"secret\"\n}",
+            ));
+            snippets.push((
+                "Create resource",
+                "{\n  \"name\": \"example\",\n  \"description\": \"\",\n  \"tags\": []\n}",
+            ));
+        }
+    }
+    snippets
+}
+
+/// Quick picks for `Settings::accept_language`, as (header value, label)
+const ACCEPT_LANGUAGE_PRESETS: [(&str, &str); 8] = [
+    ("", "Not sent"),
+    ("en-US,en;q=0.9", "English (US)"),
+    ("en-GB,en;q=0.9", "English (UK)"),
+    ("de-DE,de;q=0.9", "German"),
+    ("fr-FR,fr;q=0.9", "French"),
+    ("es-ES,es;q=0.9", "Spanish"),
+    ("ja-JP,ja;q=0.9", "Japanese"),
+    ("zh-CN,zh;q=0.9", "Chinese (Simplified)"),
+];
+
+const CONTENT_TYPES: [ContentType; 4] = [
+    ContentType::Json,
+    ContentType::Auto,
+    ContentType::FormData,
+    ContentType::FormUrlEncoded,
+];
+
+fn content_type_label(content_type: &ContentType) -> &'static str {
+    match content_type {
+        ContentType::Json => "JSON",
+        ContentType::Auto => "Auto",
+        ContentType::FormData => "Form Data",
+        ContentType::FormUrlEncoded => "Form Encoded",
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            show_metrics_bar: true,
+            format_on_paste: true,
+            keep_response_tab: false,
+            sync_json_views: true,
+            collapse_body_over_kb: Some(1024),
+            background_formatting: true,
+            accept_encoding: String::new(),
+            accept_language: String::new(),
+            max_concurrent_requests: 1,
+            strict_header_trim: true,
+            split_pasted_query: true,
+            split_url: false,
+            default_method: HttpMethod::GET,
+            default_content_type: ContentType::Json,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout_secs: Some(90),
+            pretty_by_format: BTreeMap::new(),
+            shortcuts: BTreeMap::new(),
+        }
+    }
 }
 
 #[derive(PartialEq, Clone)]
@@ -53,38 +362,246 @@ enum LayoutMode {
     Vertical,
 }
 
-#[derive(PartialEq, Clone, Debug)]
-enum HttpMethod {
-    GET,
-    POST,
-    PUT,
-    DELETE,
-    PATCH,
-}
-
 #[derive(PartialEq)]
 enum ResponseTab {
     None,
     Body,
     Headers,
+    Trailers,
+    Paths,
+    Tree,
+}
+
+/// Result of searching the response tree, as JSON pointer paths.
+#[derive(Default)]
+struct TreeMatches {
+    query: String,
+    /// Nodes whose key or scalar value contains the query
+    matched: std::collections::HashSet<String>,
+    /// Every ancestor of a matched node, kept expanded
+    on_path: std::collections::HashSet<String>,
+}
+
+/// Selected node of the response tree, and what happened to it this frame.
+struct TreeSelection<'a> {
+    selected: Option<&'a str>,
+    /// Open the selected node's ancestors and scroll it into view
+    reveal: bool,
+    /// Node clicked this frame, and whether it was a double-click
+    clicked: Option<(String, bool)>,
+}
+
+/// Children rendered per tree node before the rest are summarized
+const MAX_TREE_CHILDREN: usize = 500;
+
+/// Stats shown in the metrics bar for the latest completed response.
+struct ResponseMetrics {
+    elapsed: Duration,
+    /// Client-side decoding and formatting, on top of `elapsed`
+    format_time: Option<Duration>,
+    request_size: Option<usize>,
+    response_size: usize,
+    http_version: String,
+    content_encoding: String,
+    /// Why the content encoding was left undecoded, if it was
+    encoding_note: String,
+    /// Bytes per second the request was throttled to
+    throttle: Option<u64>,
+}
+
+/// Outcome of the latest dry run, which builds the request without sending it.
+struct DryRun {
+    /// Method and final URL
+    target: String,
+    /// Body size when known, or why the request couldn't be built
+    built: Result<Option<usize>, String>,
+    warnings: Vec<String>,
+}
+
+/// How a header echoed back by TRACE compares with the one sent.
+enum EchoedHeader {
+    Unchanged,
+    /// Not among the headers sent
+    Added,
+    /// Sent with this other value
+    Changed(String),
+}
+
+/// The request a TRACE response echoed back, checked against what was sent.
+struct TraceEcho {
+    start_line: String,
+    headers: Vec<(String, String, EchoedHeader)>,
+    /// Sent headers missing from the echo
+    removed: Vec<String>,
 }
 
-struct HttpResponse {
+/// A response pinned for later responses to the same request to be checked against.
+struct Baseline {
+    method: HttpMethod,
+    url: String,
     status: String,
-    headers: String,
+    /// Body as received, so switching between pretty and raw views doesn't
+    /// count as a difference
     body: String,
-    is_binary: bool,
-    filename: String,
     bytes: Vec<u8>,
-    content_type: String,
 }
 
+/// Progress and tallies for sending the current request several times over.
+struct RepeatRun {
+    count: usize,
+    total: usize,
+    completed: usize,
+    statuses: std::collections::BTreeMap<String, usize>,
+    timings: Vec<Duration>,
+    cancel_flag: std::sync::Arc<std::sync::atomic::AtomicBool>,
+}
+
+impl Default for RepeatRun {
+    fn default() -> Self {
+        Self {
+            count: 10,
+            total: 0,
+            completed: 0,
+            statuses: std::collections::BTreeMap::new(),
+            timings: Vec::new(),
+            cancel_flag: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+        }
+    }
+}
+
+impl RepeatRun {
+    fn running(&self) -> bool {
+        self.completed < self.total
+    }
+}
+
+/// Re-sending the current request on a fixed interval.
+struct Polling {
+    interval_secs: u64,
+    /// When the next send is due; `None` while stopped
+    next_send: Option<std::time::Instant>,
+    /// Time left when editing paused the countdown
+    paused_remaining: Option<Duration>,
+    sent: usize,
+}
+
+impl Default for Polling {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10,
+            next_send: None,
+            paused_remaining: None,
+            sent: 0,
+        }
+    }
+}
+
+impl Polling {
+    fn running(&self) -> bool {
+        self.next_send.is_some()
+    }
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum LogLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+/// One line in the diagnostics log panel.
+struct LogEntry {
+    time: String,
+    level: LogLevel,
+    message: String,
+}
+
+/// Oldest entries are dropped past this many
+const MAX_LOG_ENTRIES: usize = 2000;
+
+/// A named group of saved requests, persisted between sessions.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct Collection {
+    name: String,
+    requests: Vec<SavedRequest>,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct SavedRequest {
+    name: String,
+    request: RequestSpec,
+}
+
+/// Position of a saved request, also the drag-and-drop payload in the sidebar.
+#[derive(Clone, Copy, PartialEq)]
+struct SavedRef {
+    collection: usize,
+    index: usize,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum RenameTarget {
+    Collection(usize),
+    Request(SavedRef),
+}
+
+/// An inline rename in the collections sidebar.
+struct Renaming {
+    target: RenameTarget,
+    text: String,
+    /// Focus the text field on the next frame
+    focus: bool,
+}
+
+enum CollectionAction {
+    /// Save the current request into a collection, or a new one
+    Save(Option<usize>),
+    NewCollection,
+    Load(SavedRef),
+    /// Move a request before `index` in `collection`, or to its end
+    Move {
+        from: SavedRef,
+        collection: usize,
+        index: Option<usize>,
+    },
+    StartRename(RenameTarget),
+    FinishRename,
+    CancelRename,
+    Delete(SavedRef),
+    DeleteCollection(usize),
+}
+
+/// Storage key for the saved collections
+const COLLECTIONS_KEY: &str = "collections";
+/// Storage key for the environments
+const ENVIRONMENTS_KEY: &str = "environments";
+
+/// A sent request and how it went, newest first in `MyApp::history`.
+struct HistoryEntry {
+    request: RequestSpec,
+    status: String,
+    elapsed: Option<Duration>,
+    time: String,
+}
+
+impl HistoryEntry {
+    /// Transport errors and 4xx/5xx responses
+    fn failed(&self) -> bool {
+        self.status == "Error" || status_code(&self.status).is_some_and(|code| code >= 400)
+    }
+}
+
+const MAX_HISTORY_ENTRIES: usize = 200;
+
+/// Bases offered in the split URL editor's recent hosts menu
+const MAX_RECENT_BASES: usize = 10;
+
 #[derive(Hash, Eq, PartialEq, Clone)]
 struct HighlightCacheKey {
     text: String,
-    search_text: String,
+    /// Source of the search regex; empty when nothing is searched
+    search_pattern: String,
     search_pos: Option<usize>,
-    case_sensitive: bool,
 }
 
 #[derive(PartialEq)]
@@ -94,6 +611,17 @@ enum FindContext {
     ResponseBody,
 }
 
+/// One entry in the find dialog's match list.
+struct FindResult {
+    location: String,
+    snippet: String,
+    /// Byte range in the searched body; `None` for header matches
+    body_range: Option<std::ops::Range<usize>>,
+}
+
+/// Longest match list the find dialog shows
+const MAX_FIND_RESULTS: usize = 500;
+
 struct FindDialog {
     open: bool,
     replace_mode: bool,
@@ -101,13 +629,105 @@ struct FindDialog {
     replace_text: String,
     case_sensitive: bool,
     whole_word: bool,
+    use_regex: bool,
+    /// Also list matches in the response headers
+    include_headers: bool,
     context: FindContext,
     current_match: usize,
     total_matches: usize,
     current_match_pos: Option<usize>,
+    results: Vec<FindResult>,
     scroll_to_match: bool,
     target_scroll_y: Option<f32>,
     line_height: f32,
+    /// Search pattern compiled for the current find text and options
+    compiled: std::cell::RefCell<Option<CompiledFind>>,
+}
+
+/// The find text compiled into a regex, with the options it was compiled for.
+struct CompiledFind {
+    find_text: String,
+    case_sensitive: bool,
+    whole_word: bool,
+    use_regex: bool,
+    /// `None` while the find text is empty
+    matcher: Option<regex::Regex>,
+    /// Why the find text isn't a valid regex, in regex mode
+    error: Option<String>,
+}
+
+impl CompiledFind {
+    fn new(find_text: &str, case_sensitive: bool, whole_word: bool, use_regex: bool) -> Self {
+        let build = |pattern: &str| {
+            let pattern = if whole_word {
+                format!(r"\b(?:{})\b", pattern)
+            } else {
+                pattern.to_string()
+            };
+            let flags = if case_sensitive { "" } else { "(?i)" };
+            regex::Regex::new(&format!("{}{}", flags, pattern))
+        };
+        let literal = || build(&regex::escape(find_text)).ok();
+        let (matcher, error) = if find_text.is_empty() {
+            (None, None)
+        } else if use_regex {
+            // An invalid regex falls back to a plain search
+            match build(find_text) {
+                Ok(re) => (Some(re), None),
+                Err(e) => (literal(), Some(e.to_string())),
+            }
+        } else {
+            (literal(), None)
+        };
+        Self {
+            find_text: find_text.to_string(),
+            case_sensitive,
+            whole_word,
+            use_regex,
+            matcher,
+            error,
+        }
+    }
+}
+
+impl FindDialog {
+    /// The compiled search, rebuilt only when the find text or an option
+    /// changes.
+    fn compiled(&self) -> std::cell::Ref<'_, CompiledFind> {
+        let stale = self.compiled.borrow().as_ref().is_none_or(|c| {
+            c.find_text != self.find_text
+                || c.case_sensitive != self.case_sensitive
+                || c.whole_word != self.whole_word
+                || c.use_regex != self.use_regex
+        });
+        if stale {
+            *self.compiled.borrow_mut() = Some(CompiledFind::new(
+                &self.find_text,
+                self.case_sensitive,
+                self.whole_word,
+                self.use_regex,
+            ));
+        }
+        std::cell::Ref::map(self.compiled.borrow(), |c| {
+            c.as_ref().expect("compiled above")
+        })
+    }
+
+    /// The search as a regex: the pattern itself in regex mode, the escaped
+    /// find text otherwise. `None` while the find text is empty.
+    fn matcher(&self) -> Option<regex::Regex> {
+        self.compiled().matcher.clone()
+    }
+
+    /// Why the find text isn't a valid regex, in regex mode.
+    fn regex_error(&self) -> Option<String> {
+        self.compiled().error.clone()
+    }
+
+    /// Whether replacements expand `$1` and `${name}` group references.
+    fn expands_groups(&self) -> bool {
+        self.use_regex && self.regex_error().is_none()
+    }
 }
 
 impl Default for FindDialog {
@@ -119,39 +739,146 @@ impl Default for FindDialog {
             replace_text: String::new(),
             case_sensitive: false,
             whole_word: false,
+            use_regex: false,
+            include_headers: false,
             context: FindContext::None,
             current_match: 0,
             total_matches: 0,
             current_match_pos: None,
+            results: Vec::new(),
             scroll_to_match: false,
             target_scroll_y: None,
             line_height: 14.0,
+            compiled: std::cell::RefCell::new(None),
+        }
+    }
+}
+
+/// The request and response of a request tab that isn't active. The active
+/// tab's live in the `MyApp` fields of the same names, and the two are
+/// swapped by `MyApp::swap_tab_state` when switching tabs.
+#[derive(Default)]
+struct TabState {
+    request: RequestSpec,
+    request_locked: bool,
+    last_request: Option<RequestSpec>,
+    last_sent_at: Option<std::time::SystemTime>,
+    body_file: Option<std::path::PathBuf>,
+    body_watcher: Option<notify::RecommendedWatcher>,
+    body_watch_rx: Option<Receiver<notify::Result<notify::Event>>>,
+    last_auto_send: Option<std::time::Instant>,
+    auto_send_pending: bool,
+    response_status: String,
+    response_headers: String,
+    response_header_map: reqwest::header::HeaderMap,
+    response_trailers: String,
+    response_body: String,
+    response_raw_body: String,
+    markdown_override: Option<bool>,
+    show_full_body: bool,
+    is_response_binary: bool,
+    response_filename: String,
+    response_bytes: Vec<u8>,
+    response_content_type: String,
+    response_location: String,
+    response_paths: String,
+    response_json: Option<serde_json::Value>,
+    tree_search: String,
+    tree_matches: Option<TreeMatches>,
+    json_line_paths: Vec<String>,
+    selected_json_path: Option<String>,
+    reveal_tree_selection: bool,
+    body_scroll_line: Option<usize>,
+    body_cursor: Option<usize>,
+    response_metrics: Option<ResponseMetrics>,
+    schema_violations: Option<Vec<String>>,
+    sent_body_issue: Option<String>,
+    dry_run_result: Option<DryRun>,
+    trace_echo: Option<TraceEcho>,
+    baseline: Option<Baseline>,
+    security_audit: Vec<HeaderCheck>,
+    multipart_preview: Option<String>,
+    request_preview: Option<(RequestSpec, String)>,
+}
+
+impl From<RequestSpec> for TabState {
+    fn from(request: RequestSpec) -> Self {
+        Self {
+            request,
+            ..Default::default()
         }
     }
 }
 
 struct MyApp {
     // Request configuration
-    url: String,
-    method: HttpMethod,
-    headers: String,
-    body: String,
-    auth_type: AuthType,
-    bearer_token: String,
-    content_type: ContentType,
-    form_data: Vec<FormField>,
+    request: RequestSpec,
+    /// State of each open request tab. The active tab is edited through the
+    /// fields below, and its slot here only holds a placeholder.
+    request_tabs: Vec<TabState>,
+    active_request_index: usize,
+    /// The spec actually sent for the current response, host profile defaults included
+    last_request: Option<RequestSpec>,
+    last_sent_at: Option<std::time::SystemTime>,
     cancel_flag: std::sync::Arc<std::sync::atomic::AtomicBool>,
-    request_timeout: u64,
     request_start_time: Option<std::time::Instant>,
+    request_locked: bool,
+    body_file: Option<std::path::PathBuf>,
+    body_watcher: Option<notify::RecommendedWatcher>,
+    body_watch_rx: Option<Receiver<notify::Result<notify::Event>>>,
+    last_auto_send: Option<std::time::Instant>,
+    /// The watched file changed while a request was in flight; send again
+    /// once it finishes
+    auto_send_pending: bool,
+    host_profiles: Vec<HostProfile>,
+    environments: Vec<Environment>,
+    active_environment: Option<usize>,
 
     // Response data
     response_status: String,
     response_headers: String,
+    response_header_map: reqwest::header::HeaderMap,
+    response_trailers: String,
     response_body: String,
+    /// Response text as received, before any pretty printing
+    response_raw_body: String,
+    /// Render the body as Markdown (`Some(true)`) or show it raw, overriding
+    /// detection by content type until the next response
+    markdown_override: Option<bool>,
+    /// Render the whole body even when it is over the collapse size
+    show_full_body: bool,
+    markdown_cache: egui_commonmark::CommonMarkCache,
     is_response_binary: bool,
     response_filename: String,
     response_bytes: Vec<u8>,
     response_content_type: String,
+    response_location: String,
+    response_paths: String,
+    response_json: Option<serde_json::Value>,
+    tree_search: String,
+    tree_matches: Option<TreeMatches>,
+    /// Pointer path of the JSON node starting on each line of the Body text;
+    /// empty unless the Body shows the pretty-printed JSON
+    json_line_paths: Vec<String>,
+    selected_json_path: Option<String>,
+    /// Open the tree down to `selected_json_path` and scroll to it
+    reveal_tree_selection: bool,
+    /// Body line to scroll to and place the cursor on when the Body shows
+    body_scroll_line: Option<usize>,
+    /// Body cursor position last seen, to notice when it moves
+    body_cursor: Option<usize>,
+    /// Base and path of the URL in the split editor; re-split from the URL
+    /// whenever they no longer join into it
+    url_halves: (String, String),
+    response_metrics: Option<ResponseMetrics>,
+    schema_violations: Option<Vec<String>>,
+    /// Body encoding problem found when the current request was sent
+    sent_body_issue: Option<String>,
+    /// Send only builds and previews the request
+    dry_run: bool,
+    dry_run_result: Option<DryRun>,
+    trace_echo: Option<TraceEcho>,
+    baseline: Option<Baseline>,
 
     // UI state
     loading: bool,
@@ -161,65 +888,238 @@ struct MyApp {
     highlight_cache: std::cell::RefCell<HashMap<HighlightCacheKey, egui::text::LayoutJob>>,
     copied: bool,
     copied_at: Option<std::time::Instant>,
+    paste_error: Option<String>,
+    /// File count and total size of directories picked for zip upload
+    directory_summaries: HashMap<String, Result<(usize, u64), String>>,
+    bundle_error: Option<String>,
+    multipart_preview: Option<String>,
+    /// Wire preview of the prepared request, recomputed when the request changes
+    request_preview: Option<(RequestSpec, String)>,
+    show_host_profiles: bool,
+    show_environments: bool,
+    env_switcher: Option<EnvSwitcher>,
+    show_settings: bool,
+    show_repeat: bool,
+    show_log: bool,
+    show_security_audit: bool,
+    /// Security header checks of the latest response
+    security_audit: Vec<HeaderCheck>,
+    show_history: bool,
+    show_collections: bool,
+    /// Action whose new shortcut is being recorded in Settings
+    recording_shortcut: Option<ShortcutAction>,
+    collections: Vec<Collection>,
+    renaming: Option<Renaming>,
+    history: Vec<HistoryEntry>,
+    history_errors_only: bool,
+    /// Editor state of the in-flight request, recorded in history on completion
+    pending_history: Option<RequestSpec>,
+    /// Entries of an imported HAR file and the file's name
+    har_import: Option<(String, Vec<HarEntry>)>,
+    har_error: Option<String>,
+    log: VecDeque<LogEntry>,
 
     //UI elements
     find_dialog: FindDialog,
 
+    // Shared HTTP client and the runtime its pooled connections live on
+    settings: Settings,
+    client: reqwest::Client,
+    runtime: tokio::runtime::Runtime,
+
     // Communication channel for async requests
     tx: Sender<HttpResponse>,
     rx: Receiver<HttpResponse>,
+
+    // Background formatting of the request body, sent back as (source, formatted)
+    formatting: bool,
+    format_tx: Sender<(String, String)>,
+    format_rx: Receiver<(String, String)>,
+
+    // Reachability check for the URL's host
+    checking_connection: bool,
+    connection_check: Option<Result<ConnectionCheck, String>>,
+    check_tx: Sender<Result<ConnectionCheck, String>>,
+    check_rx: Receiver<Result<ConnectionCheck, String>>,
+
+    // Repeat runs report each response on their own channel
+    repeat: RepeatRun,
+    polling: Polling,
+    repeat_tx: Sender<HttpResponse>,
+    repeat_rx: Receiver<HttpResponse>,
 }
 
 impl Default for MyApp {
     fn default() -> Self {
         let (tx, rx) = mpsc::channel();
+        let (format_tx, format_rx) = mpsc::channel();
+        let (repeat_tx, repeat_rx) = mpsc::channel();
+        let (check_tx, check_rx) = mpsc::channel();
+        let settings = Settings::default();
         Self {
-            url: "https://jsonplaceholder.typicode.com/posts".to_string(),
-            method: HttpMethod::GET,
-            headers: "# Add headers as key: value pairs\n# Example:\n# X-Custom-Header: value"
-                .to_string(),
-            body: r#"{
+            request: settings.with_request_defaults(
+                RequestSpec {
+                    headers:
+                        "# Add headers as key: value pairs\n# Example:\n# X-Custom-Header: value"
+                            .to_string(),
+                    cookies: vec![CookieRow::default()],
+                    form_data: vec![FormField::default()],
+                    ..RequestSpec::from("https://jsonplaceholder.typicode.com/posts")
+                }
+                .json_body(
+                    r#"{
   "title": "foo",
   "body": "bar",
   "userId": 1
-}"#
-            .to_string(),
+}"#,
+                ),
+            ),
+            request_tabs: vec![TabState::default()],
+            active_request_index: 0,
+            last_request: None,
+            last_sent_at: None,
             cancel_flag: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
-            request_timeout: 30,
             request_start_time: None,
+            request_locked: false,
+            body_file: None,
+            body_watcher: None,
+            body_watch_rx: None,
+            last_auto_send: None,
+            auto_send_pending: false,
+            host_profiles: Vec::new(),
+            environments: Vec::new(),
+            active_environment: None,
             response_status: String::new(),
             response_headers: String::new(),
+            response_header_map: reqwest::header::HeaderMap::new(),
+            response_trailers: String::new(),
             response_body: String::new(),
+            response_raw_body: String::new(),
+            markdown_override: None,
+            show_full_body: false,
+            markdown_cache: egui_commonmark::CommonMarkCache::default(),
             is_response_binary: false,
             response_filename: String::new(),
             response_bytes: Vec::new(),
             response_content_type: String::new(),
+            response_location: String::new(),
+            response_paths: String::new(),
+            response_json: None,
+            tree_search: String::new(),
+            tree_matches: None,
+            json_line_paths: Vec::new(),
+            selected_json_path: None,
+            reveal_tree_selection: false,
+            body_scroll_line: None,
+            body_cursor: None,
+            url_halves: (String::new(), String::new()),
+            response_metrics: None,
+            schema_violations: None,
+            sent_body_issue: None,
+            dry_run: false,
+            dry_run_result: None,
+            trace_echo: None,
+            baseline: None,
             loading: false,
             copied: false,
             copied_at: None,
+            paste_error: None,
+            directory_summaries: HashMap::new(),
+            bundle_error: None,
+            multipart_preview: None,
+            request_preview: None,
+            show_host_profiles: false,
+            show_environments: false,
+            env_switcher: None,
+            show_settings: false,
+            show_repeat: false,
+            show_log: false,
+            show_security_audit: false,
+            security_audit: Vec::new(),
+            show_history: false,
+            show_collections: false,
+            recording_shortcut: None,
+            collections: Vec::new(),
+            renaming: None,
+            history: Vec::new(),
+            history_errors_only: false,
+            pending_history: None,
+            har_import: None,
+            har_error: None,
+            log: VecDeque::new(),
+            repeat: RepeatRun::default(),
+            polling: Polling::default(),
+            repeat_tx,
+            repeat_rx,
+            checking_connection: false,
+            connection_check: None,
+            check_tx,
+            check_rx,
             highlight_cache: std::cell::RefCell::new(HashMap::new()),
             layout_mode: LayoutMode::Horizontal,
             active_request_tab: RequestTab::Body,
             active_response_tab: ResponseTab::None,
             find_dialog: FindDialog::default(),
-            auth_type: AuthType::None,
-            bearer_token: String::new(),
-            content_type: ContentType::Json,
-            form_data: vec![FormField {
-                key: String::new(),
-                value: String::new(),
-                files: Vec::new(),
-                field_type: FormFieldType::Text,
-            }],
+            client: build_client(&settings),
+            runtime: tokio::runtime::Runtime::new().expect("Failed to start async runtime"),
+            settings,
             tx,
             rx,
+            formatting: false,
+            format_tx,
+            format_rx,
         }
     }
 }
 impl MyApp {
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    fn new(cc: &eframe::CreationContext<'_>, args: CliArgs) -> Self {
         egui_extras::install_image_loaders(&cc.egui_ctx);
-        Self::default()
+        let mut app = Self::default();
+        if let Some(storage) = cc.storage {
+            if let Some(saved) = storage.get_string(SETTINGS_KEY)
+                && let Ok(settings) = serde_json::from_str(&saved)
+            {
+                app.settings = settings;
+                // The default client was built before the saved pool settings were known
+                app.client = build_client(&app.settings);
+            }
+            if let Some(saved) = storage.get_string(COLLECTIONS_KEY)
+                && let Ok(collections) = serde_json::from_str(&saved)
+            {
+                app.collections = collections;
+            }
+            if let Some(saved) = storage.get_string(ENVIRONMENTS_KEY)
+                && let Ok(environments) = serde_json::from_str(&saved)
+            {
+                app.environments = environments;
+            }
+            app.request = app
+                .settings
+                .with_request_defaults(std::mem::take(&mut app.request));
+        }
+        app.apply_cli_args(args);
+        app
+    }
+
+    fn apply_cli_args(&mut self, args: CliArgs) {
+        if let Some(url) = args.url {
+            self.request.url = url;
+        }
+        if let Some(method) = args.method {
+            self.request.method = method;
+        }
+        for header in args.headers {
+            self.request.headers.push('\n');
+            self.request.headers.push_str(&header);
+        }
+        if let Some(body) = args.data {
+            self.request.body = body;
+            self.request.content_type = ContentType::Json;
+            self.active_request_tab = RequestTab::Body;
+        }
+        if args.send && !self.request.url.trim().is_empty() {
+            self.send_request();
+        }
     }
 
     fn name() -> &'static str {
@@ -227,9 +1127,32 @@ impl MyApp {
     }
 
     fn prettify_json(&mut self) {
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&self.body) {
-            if let Ok(pretty) = serde_json::to_string_pretty(&json) {
-                self.body = pretty;
+        if self.request_locked {
+            return;
+        }
+        if !self.settings.background_formatting
+            || self.request.body.len() < BACKGROUND_FORMAT_THRESHOLD
+        {
+            self.request.body = pretty_json(std::mem::take(&mut self.request.body));
+            return;
+        }
+
+        // Large bodies are formatted off the UI thread so it doesn't hitch
+        let source = self.request.body.clone();
+        let tx = self.format_tx.clone();
+        self.formatting = true;
+        self.runtime.spawn_blocking(move || {
+            let pretty = pretty_json(source.clone());
+            let _ = tx.send((source, pretty));
+        });
+    }
+
+    fn poll_formatting(&mut self) {
+        if let Ok((source, pretty)) = self.format_rx.try_recv() {
+            self.formatting = false;
+            // Drop the result if the body was edited while it was being formatted
+            if self.request.body == source {
+                self.request.body = pretty;
             }
         }
     }
@@ -237,15 +1160,13 @@ impl MyApp {
     fn memoized_highlight_json(
         cache: &std::cell::RefCell<HashMap<HighlightCacheKey, egui::text::LayoutJob>>,
         text: &str,
-        search_text: &str,
+        search: Option<&regex::Regex>,
         search_pos: Option<usize>,
-        case_sensitive: bool,
     ) -> egui::text::LayoutJob {
         let key = HighlightCacheKey {
             text: text.to_string(),
-            search_text: search_text.to_string(),
+            search_pattern: search.map(|re| re.as_str().to_string()).unwrap_or_default(),
             search_pos,
-            case_sensitive,
         };
 
         // Try cache first
@@ -254,7 +1175,7 @@ impl MyApp {
         }
 
         // Compute
-        let result = highlight_json_with_search(text, search_text, search_pos, case_sensitive);
+        let result = highlight_json_with_search(text, search, search_pos);
 
         // Insert into cache (evict if too big)
         {
@@ -268,29 +1189,89 @@ impl MyApp {
         result
     }
 
-    fn parse_headers(&self) -> reqwest::header::HeaderMap {
-        let mut headers = reqwest::header::HeaderMap::new();
+    /// Variables of the active environment.
+    fn environment_variables(&self) -> Vec<(String, String)> {
+        self.active_environment
+            .and_then(|i| self.environments.get(i))
+            .map(|env| parse_variables(&env.variables))
+            .unwrap_or_default()
+    }
 
-        for line in self.headers.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') {
-                continue;
-            }
+    /// The URL with the active environment's variables filled in, as sent.
+    fn resolved_url(&self) -> String {
+        substitute_variables(&self.request.url, &self.environment_variables())
+    }
 
-            if let Some((key, value)) = line.split_once(':') {
-                let key = key.trim();
-                let value = value.trim();
+    fn matching_host_profile(&self) -> Option<&HostProfile> {
+        let url = self.resolved_url();
+        let url = reqwest::Url::parse(url.trim()).ok()?;
+        let host = url.host_str()?;
+        self.host_profiles
+            .iter()
+            .find(|p| !p.host.trim().is_empty() && p.host.trim().eq_ignore_ascii_case(host))
+    }
 
-                if let (Ok(header_name), Ok(header_value)) = (
-                    reqwest::header::HeaderName::from_bytes(key.as_bytes()),
-                    reqwest::header::HeaderValue::from_str(value),
-                ) {
-                    headers.insert(header_name, header_value);
+    fn render_multipart_preview(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Raw multipart")
+            .id_salt("raw_multipart")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Boundary:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.request.multipart_boundary)
+                            .hint_text("random per request")
+                            .desired_width(260.0),
+                    );
+                });
+                let boundary = self.request.multipart_boundary.trim();
+                if !boundary.is_empty()
+                    && let Err(e) = client::validate_boundary(boundary)
+                {
+                    ui.colored_label(
+                        egui::Color32::from_rgb(200, 100, 100),
+                        format!("⚠ Invalid boundary: {}", e),
+                    );
                 }
-            }
-        }
 
-        headers
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("🔍 Preview body")
+                        .on_hover_text("Assemble the body exactly as it will be sent")
+                        .clicked()
+                    {
+                        let boundary = if self.request.multipart_boundary.trim().is_empty() {
+                            "{random-boundary}"
+                        } else {
+                            self.request.multipart_boundary.trim()
+                        };
+                        self.multipart_preview = Some(
+                            match client::assemble_multipart(
+                                &self.request.form_data,
+                                boundary,
+                                true,
+                            ) {
+                                Ok(body) => String::from_utf8_lossy(&body).into_owned(),
+                                Err(e) => format!("Can't assemble the body: {}", e),
+                            },
+                        );
+                    }
+                    if self.multipart_preview.is_some() && ui.button("Hide").clicked() {
+                        self.multipart_preview = None;
+                    }
+                });
+
+                if let Some(preview) = &self.multipart_preview {
+                    ui.label(
+                        egui::RichText::new("Binary or large files are shown as placeholders")
+                            .weak(),
+                    );
+                    ui.add(
+                        egui::TextEdit::multiline(&mut preview.as_str())
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                }
+            });
     }
 
     fn render_request_section(&mut self, ui: &mut egui::Ui) {
@@ -299,34 +1280,62 @@ impl MyApp {
             .inner_margin(egui::Margin::same(10))
             .show(ui, |ui| {
                 ui.expand_to_include_rect(ui.max_rect());
-                ui.strong("Request");
+                ui.horizontal(|ui| {
+                    ui.strong("Request");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.request.expected_status)
+                                .hint_text("e.g. 201")
+                                .desired_width(50.0),
+                        );
+                        ui.label("Expect status:");
+                    });
+                });
                 ui.add_space(6.0);
 
                 // Tabs
                 ui.horizontal(|ui| {
-                    if matches!(
-                        self.method,
-                        HttpMethod::POST | HttpMethod::PUT | HttpMethod::PATCH
-                    ) {
+                    if self.request.method.has_body() {
                         ui.selectable_value(&mut self.active_request_tab, RequestTab::Body, "Body");
                     }
+                    let params = self.request.params.iter().filter(|p| p.enabled).count();
+                    ui.selectable_value(
+                        &mut self.active_request_tab,
+                        RequestTab::Params,
+                        if params > 0 {
+                            format!("Params ({})", params)
+                        } else {
+                            "Params".to_string()
+                        },
+                    );
                     ui.selectable_value(
                         &mut self.active_request_tab,
                         RequestTab::Headers,
                         "Headers",
                     );
                     ui.selectable_value(&mut self.active_request_tab, RequestTab::Auth, "Auth");
+                    ui.selectable_value(
+                        &mut self.active_request_tab,
+                        RequestTab::Cookies,
+                        "Cookies",
+                    );
+                    ui.selectable_value(&mut self.active_request_tab, RequestTab::Schema, "Schema")
+                        .on_hover_text("Validate JSON responses against a JSON Schema");
+                    ui.selectable_value(&mut self.active_request_tab, RequestTab::Preview, "Preview")
+                        .on_hover_text("Exactly what will be sent");
                 });
 
                 ui.separator();
                 ui.add_space(4.0);
 
+                // Tabs stay switchable while locked, but the editors do not
+                if self.request_locked {
+                    ui.disable();
+                }
+
                 match self.active_request_tab {
                     RequestTab::Body => {
-                        if !matches!(
-                            self.method,
-                            HttpMethod::POST | HttpMethod::PUT | HttpMethod::PATCH
-                        ) {
+                        if !self.request.method.has_body() {
                             ui.label("Select POST, PUT, or PATCH to edit body.");
                             return;
                         }
@@ -334,48 +1343,182 @@ impl MyApp {
                         ui.horizontal(|ui| {
                             ui.label("Type:");
                             egui::ComboBox::from_id_salt("content_type")
-                                .selected_text(if self.content_type == ContentType::Json {
-                                    "JSON"
-                                } else if self.content_type == ContentType::FormUrlEncoded {
-                                    "Form Encoded"
-                                } else {
-                                    "Form Data"
-                                })
+                                .selected_text(content_type_label(&self.request.content_type))
                                 .show_ui(ui, |ui| {
                                     ui.selectable_value(
-                                        &mut self.content_type,
+                                        &mut self.request.content_type,
                                         ContentType::Json,
                                         "JSON",
                                     );
                                     ui.selectable_value(
-                                        &mut self.content_type,
+                                        &mut self.request.content_type,
+                                        ContentType::Auto,
+                                        "Auto",
+                                    )
+                                    .on_hover_text("Pick the Content-Type from the body text");
+                                    ui.selectable_value(
+                                        &mut self.request.content_type,
                                         ContentType::FormData,
                                         "Form Data",
                                     );
                                     ui.selectable_value(
-                                        &mut self.content_type,
+                                        &mut self.request.content_type,
                                         ContentType::FormUrlEncoded,
                                         "Form Encoded",
                                     );
                                 });
 
+                            if self.request.content_type == ContentType::Auto {
+                                ui.label(
+                                    egui::RichText::new(format!(
+                                        "→ {}",
+                                        self.request.raw_body_mime()
+                                    ))
+                                    .weak(),
+                                )
+                                .on_hover_text("Content-Type detected from the body");
+                            }
+
+                            // APIs are picky about which JSON flavour a PATCH carries
+                            if self.request.method == HttpMethod::PATCH
+                                && self.request.content_type.is_raw()
+                            {
+                                egui::ComboBox::from_id_salt("patch_format")
+                                    .selected_text(self.request.patch_format.mime())
+                                    .show_ui(ui, |ui| {
+                                        for format in [
+                                            PatchFormat::Json,
+                                            PatchFormat::MergePatch,
+                                            PatchFormat::JsonPatch,
+                                        ] {
+                                            ui.selectable_value(
+                                                &mut self.request.patch_format,
+                                                format,
+                                                format.mime(),
+                                            );
+                                        }
+                                    });
+                            }
+
                             ui.with_layout(
                                 egui::Layout::right_to_left(egui::Align::Center),
                                 |ui| {
-                                    if self.content_type == ContentType::Json {
-                                        if ui.button("✨ Prettify").clicked() {
+                                    if self.request.content_type.is_raw() {
+                                        if self.formatting {
+                                            ui.add_enabled(
+                                                false,
+                                                egui::Button::new("⏳ Formatting…"),
+                                            );
+                                        } else if ui.button("✨ Prettify").clicked() {
                                             self.prettify_json();
                                         }
-                                    }
-                                },
+                                        if self.request.body.trim().is_empty() {
+                                            ui.menu_button("📝 Examples", |ui| {
+                                                for (label, body) in body_snippets(
+                                                    &self.request.method,
+                                                    self.request.patch_format,
+                                                ) {
+                                                    if ui.button(label).clicked() {
+                                                        self.request.body = body.to_string();
+                                                        ui.close();
+                                                    }
+                                                }
+                                            })
+                                            .response
+                                            .on_hover_text("Start from an example body");
+                                        }
+                                        if ui
+                                            .button("📂 Load")
+                                            .on_hover_text("Load the body from a file")
+                                            .clicked()
+                                            && let Some(path) = rfd::FileDialog::new().pick_file()
+                                        {
+                                            self.body_file = Some(path);
+                                            self.load_body_from_file();
+                                            if self.body_watcher.is_some() {
+                                                self.set_body_watch(ui.ctx(), true);
+                                            }
+                                        }
+                                    }
+                                },
                             );
                         });
+
+                        if self.request.method == HttpMethod::PATCH
+                            && self.request.content_type.is_raw()
+                            && self.request.raw_body_mime() == PatchFormat::JsonPatch.mime()
+                            && let Err(e) = validate_json_patch(&self.request.body)
+                        {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(255, 165, 0),
+                                format!("⚠ Not a valid JSON Patch: {}", e),
+                            );
+                        }
+
+                        if self.request.content_type.is_raw()
+                            && let Some(issue) = body_encoding_issue(&self.request.body)
+                        {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(255, 165, 0),
+                                    format!("⚠ Body {}", issue),
+                                );
+                                if leading_noise(&self.request.body).contains(is_invisible_noise)
+                                    && ui
+                                        .small_button("Strip")
+                                        .on_hover_text(
+                                            "Remove the BOM and invisible characters before the body",
+                                        )
+                                        .clicked()
+                                {
+                                    self.request.body = strip_body_noise(&self.request.body);
+                                }
+                            });
+                        }
+
+                        if self.request.content_type.is_raw()
+                            && let Some(path) = self.body_file.clone()
+                        {
+                            ui.horizontal(|ui| {
+                                let name = path
+                                    .file_name()
+                                    .and_then(|n| n.to_str())
+                                    .unwrap_or_default();
+                                ui.label(format!("📄 {}", name))
+                                    .on_hover_text(path.display().to_string());
+
+                                let mut watching = self.body_watcher.is_some();
+                                if ui
+                                    .toggle_value(&mut watching, "👁 Watch")
+                                    .on_hover_text("Re-send whenever the file changes on disk")
+                                    .changed()
+                                {
+                                    self.set_body_watch(ui.ctx(), watching);
+                                }
+
+                                if let Some(t) = self.last_auto_send {
+                                    ui.label(
+                                        egui::RichText::new(format!(
+                                            "auto-sent {}s ago",
+                                            t.elapsed().as_secs()
+                                        ))
+                                        .weak(),
+                                    );
+                                }
+
+                                if ui.small_button("✖").on_hover_text("Forget file").clicked() {
+                                    self.set_body_watch(ui.ctx(), false);
+                                    self.body_file = None;
+                                    self.last_auto_send = None;
+                                }
+                            });
+                        }
                         ui.add_space(6.0);
 
                         egui::ScrollArea::vertical()
                             .id_salt("request_scroll")
-                            .show(ui, |ui| match self.content_type {
-                                ContentType::Json => {
+                            .show(ui, |ui| match self.request.content_type {
+                                ContentType::Json | ContentType::Auto => {
                                     let line_height =
                                         ui.text_style_height(&egui::TextStyle::Monospace);
                                     let rows =
@@ -383,39 +1526,41 @@ impl MyApp {
 
                                     ui.expand_to_include_rect(ui.max_rect());
 
-                                    ui.add(
-                                        egui::TextEdit::multiline(&mut self.body)
+                                    let response = ui.add(
+                                        egui::TextEdit::multiline(&mut self.request.body)
                                             .code_editor()
                                             .desired_width(f32::INFINITY)
                                             .desired_rows(rows)
                                             .layouter(&mut |ui, text, wrap_width| {
-                                                let job = if self.find_dialog.open
+                                                let search = (self.find_dialog.open
                                                     && self.find_dialog.context
-                                                        == FindContext::RequestBody
-                                                    && !self.find_dialog.find_text.is_empty()
-                                                {
-                                                    MyApp::memoized_highlight_json(
-                                                        &self.highlight_cache,
-                                                        text.as_str(),
-                                                        &self.find_dialog.find_text,
-                                                        self.find_dialog.current_match_pos,
-                                                        self.find_dialog.case_sensitive,
-                                                    )
-                                                } else {
-                                                    MyApp::memoized_highlight_json(
-                                                        &self.highlight_cache,
-                                                        text.as_str(),
-                                                        "",
-                                                        None,
-                                                        false,
-                                                    )
-                                                };
-
-                                                let mut job = job;
+                                                        == FindContext::RequestBody)
+                                                    .then(|| self.find_dialog.matcher())
+                                                    .flatten();
+                                                let mut job = MyApp::memoized_highlight_json(
+                                                    &self.highlight_cache,
+                                                    text.as_str(),
+                                                    search.as_ref(),
+                                                    self.find_dialog.current_match_pos,
+                                                );
+
                                                 job.wrap.max_width = wrap_width;
                                                 ui.fonts_mut(|f| f.layout_job(job))
                                             }),
                                     );
+
+                                    // Reformat right after a JSON blob is pasted in
+                                    if response.changed()
+                                        && self.settings.format_on_paste
+                                        && ui.input(|i| {
+                                            i.events.iter().any(|e| {
+                                                matches!(e, egui::Event::Paste(text) if is_json_document(text))
+                                            })
+                                        })
+                                        && is_json_document(&self.request.body)
+                                    {
+                                        self.prettify_json();
+                                    }
                                 }
                                 ContentType::FormData | ContentType::FormUrlEncoded => {
                                     ui.set_max_width(ui.available_width());
@@ -425,7 +1570,7 @@ impl MyApp {
                                         |ui| {
                                             let mut to_remove = None;
 
-                                            for (i, field) in self.form_data.iter_mut().enumerate()
+                                            for (i, field) in self.request.form_data.iter_mut().enumerate()
                                             {
                                                 ui.horizontal_wrapped(|ui| {
                                                     ui.label("Key:");
@@ -438,7 +1583,7 @@ impl MyApp {
                                                     );
 
                                                     // Only show combo box for FormData (multipart)
-                                                    if self.content_type == ContentType::FormData {
+                                                    if self.request.content_type == ContentType::FormData {
                                                         egui::ComboBox::from_id_salt(format!(
                                                             "field_type_{}",
                                                             i
@@ -489,23 +1634,52 @@ impl MyApp {
                                                         }
                                                         FormFieldType::File => {
                                                             // Only allow file selection for FormData
-                                                            if self.content_type
+                                                            if self.request.content_type
                                                                 == ContentType::FormData
                                                             {
                                                                 ui.label("File:");
                                                                 if ui.button("📁 Choose").clicked()
-                                                                {
-                                                                    if let Some(paths) =
+                                                                    && let Some(paths) =
                                                                         rfd::FileDialog::new()
                                                                             .pick_files()
+                                                                {
+                                                                    field.files = paths
+                                                                        .into_iter()
+                                                                        .map(|p| {
+                                                                            p.display().to_string()
+                                                                        })
+                                                                        .collect();
+                                                                }
+                                                                if ui
+                                                                    .button("🗀 Folder")
+                                                                    .on_hover_text(
+                                                                        "Send a directory as one .zip part",
+                                                                    )
+                                                                    .clicked()
+                                                                    && let Some(dir) =
+                                                                        rfd::FileDialog::new()
+                                                                            .pick_folder()
+                                                                {
+                                                                    let dir = dir.display().to_string();
+                                                                    self.directory_summaries.remove(&dir);
+                                                                    field.files.push(dir);
+                                                                }
+                                                                if ui
+                                                                    .button("📋 Paste")
+                                                                    .on_hover_text(
+                                                                        "Paste a file or image from the clipboard",
+                                                                    )
+                                                                    .clicked()
+                                                                {
+                                                                    match paste_files_from_clipboard()
                                                                     {
-                                                                        field.files = paths
-                                                                            .into_iter()
-                                                                            .map(|p| {
-                                                                                p.display()
-                                                                                    .to_string()
-                                                                            })
-                                                                            .collect();
+                                                                        Ok(paths) => {
+                                                                            field.files.extend(paths);
+                                                                            self.paste_error = None;
+                                                                        }
+                                                                        Err(e) => {
+                                                                            self.paste_error = Some(e);
+                                                                        }
                                                                     }
                                                                 }
                                                                 if !field.files.is_empty() {
@@ -538,19 +1712,47 @@ impl MyApp {
                                                 });
 
                                                 // Show selected files (only for FormData)
-                                                if self.content_type == ContentType::FormData
+                                                if self.request.content_type == ContentType::FormData
                                                     && field.field_type == FormFieldType::File
                                                     && !field.files.is_empty()
                                                 {
                                                     ui.indent(format!("files_{}", i), |ui| {
                                                         for file in &field.files {
-                                                            ui.label(format!(
-                                                                "  • {}",
-                                                                std::path::Path::new(file)
-                                                                    .file_name()
-                                                                    .and_then(|n| n.to_str())
-                                                                    .unwrap_or(file)
-                                                            ));
+                                                            let path = std::path::Path::new(file);
+                                                            let name = path
+                                                                .file_name()
+                                                                .and_then(|n| n.to_str())
+                                                                .unwrap_or(file);
+                                                            if !path.is_dir() {
+                                                                ui.label(format!("  • {}", name));
+                                                                continue;
+                                                            }
+                                                            let summary = self
+                                                                .directory_summaries
+                                                                .entry(file.clone())
+                                                                .or_insert_with(|| {
+                                                                    client::directory_summary(path)
+                                                                        .map_err(|e| e.to_string())
+                                                                });
+                                                            match summary {
+                                                                Ok((count, size)) => ui.label(
+                                                                    format!(
+                                                                        "  • {}.zip ({} files, {})",
+                                                                        name,
+                                                                        count,
+                                                                        format_size(*size as usize)
+                                                                    ),
+                                                                ),
+                                                                Err(e) => ui.colored_label(
+                                                                    egui::Color32::from_rgb(
+                                                                        200, 100, 100,
+                                                                    ),
+                                                                    format!("  • {}: {}", name, e),
+                                                                ),
+                                                            }
+                                                            .on_hover_text(
+                                                                "Zipped when the request is sent",
+                                                            );
                                                         }
                                                     });
                                                 }
@@ -562,19 +1764,60 @@ impl MyApp {
 
                                             // Remove field if requested
                                             if let Some(i) = to_remove {
-                                                self.form_data.remove(i);
+                                                self.request.form_data.remove(i);
+                                            }
+
+                                            if let Some(err) = &self.paste_error {
+                                                ui.colored_label(
+                                                    egui::Color32::from_rgb(200, 100, 100),
+                                                    format!("Paste failed: {}", err),
+                                                );
                                             }
 
                                             ui.add_space(6.0);
 
-                                            // Add new field button
-                                            if ui.button("➕ Add Field").clicked() {
-                                                self.form_data.push(FormField {
-                                                    key: String::new(),
-                                                    value: String::new(),
-                                                    files: Vec::new(),
-                                                    field_type: FormFieldType::Text,
+                                            ui.horizontal(|ui| {
+                                                // Add new field button
+                                                if ui.button("➕ Add Field").clicked() {
+                                                    self.request.form_data.push(FormField {
+                                                        key: String::new(),
+                                                        value: String::new(),
+                                                        files: Vec::new(),
+                                                        field_type: FormFieldType::Text,
+                                                    });
+                                                }
+                                                let multipart = self.request.content_type
+                                                    == ContentType::FormData;
+                                                ui.menu_button("📝 Common fields", |ui| {
+                                                    for (label, fields) in FORM_FIELD_PRESETS {
+                                                        let has_file = fields
+                                                            .iter()
+                                                            .any(|(_, t)| *t == FormFieldType::File);
+                                                        if (multipart || !has_file)
+                                                            && ui.button(label).clicked()
+                                                        {
+                                                            // Replace the blank starter row rather than keep it
+                                                            self.request
+                                                                .form_data
+                                                                .retain(|f| *f != FormField::default());
+                                                            self.request.form_data.extend(
+                                                                fields.iter().map(|(key, field_type)| {
+                                                                    FormField {
+                                                                        key: key.to_string(),
+                                                                        field_type: field_type.clone(),
+                                                                        ..Default::default()
+                                                                    }
+                                                                }),
+                                                            );
+                                                            ui.close();
+                                                        }
+                                                    }
                                                 });
+                                            });
+
+                                            if self.request.content_type == ContentType::FormData {
+                                                ui.add_space(6.0);
+                                                self.render_multipart_preview(ui);
                                             }
                                         },
                                     );
@@ -592,60 +1835,265 @@ impl MyApp {
 
                         ui.expand_to_include_rect(ui.max_rect());
 
-                        egui::TextEdit::multiline(&mut self.headers)
-                            .code_editor()
-                            .hint_text("# Key: Value\n# Content-Type: application/json")
-                            .desired_width(f32::INFINITY)
-                            .desired_rows(rows)
-                            .show(ui);
+                        // Soft-wrap long values such as cookies or tokens anywhere, since they
+                        // have no spaces to break on. Wrapping is visual only, so each header
+                        // is still a single line for parse_header_lines.
+                        let mut layouter =
+                            |ui: &egui::Ui, text: &dyn egui::TextBuffer, wrap_width: f32| {
+                                let mut job = egui::text::LayoutJob::simple(
+                                    text.as_str().to_owned(),
+                                    egui::TextStyle::Monospace.resolve(ui.style()),
+                                    ui.visuals().text_color(),
+                                    wrap_width,
+                                );
+                                job.wrap.break_anywhere = true;
+                                ui.fonts_mut(|f| f.layout_job(job))
+                            };
+
+                        egui::ScrollArea::vertical()
+                            .id_salt("headers_scroll")
+                            .show(ui, |ui| {
+                                let output = egui::TextEdit::multiline(&mut self.request.headers)
+                                    .code_editor()
+                                    .hint_text("# Key: Value\n# Content-Type: application/json")
+                                    .desired_width(f32::INFINITY)
+                                    .desired_rows(rows)
+                                    .layouter(&mut layouter)
+                                    .show(ui);
+
+                                // Ctrl+/ comments out or restores the selected header lines
+                                if output.response.has_focus()
+                                    && ui.input(|i| {
+                                        i.modifiers.ctrl && i.key_pressed(egui::Key::Slash)
+                                    })
+                                    && let Some(range) = output.cursor_range
+                                {
+                                    let start = range.primary.index.min(range.secondary.index);
+                                    let end = range.primary.index.max(range.secondary.index);
+                                    self.request.headers = client::toggle_line_comments(&self.request.headers, start, end);
+                                }
+
+                                let parsed = parse_header_lines_with(
+                                    &self.request.headers,
+                                    self.settings.strict_header_trim,
+                                );
+                                for skipped in parsed.skipped {
+                                    ui.colored_label(
+                                        egui::Color32::from_rgb(255, 165, 0),
+                                        format!(
+                                            "⚠ Line {} skipped ({}): {}",
+                                            skipped.line,
+                                            skipped.reason,
+                                            skipped.text.trim()
+                                        ),
+                                    );
+                                }
+                            });
+                    }
+                    RequestTab::Preview => {
+                        let spec = self.prepared_request();
+                        if self
+                            .request_preview
+                            .as_ref()
+                            .is_none_or(|(previewed, _)| *previewed != spec)
+                        {
+                            let text = client::preview_request(&spec);
+                            self.request_preview = Some((spec, text));
+                        }
+                        let Some((_, preview)) = &self.request_preview else {
+                            return;
+                        };
+
+                        ui.horizontal(|ui| {
+                            ui.label(
+                                egui::RichText::new(
+                                    "Host profile and settings defaults included; the client may add connection headers",
+                                )
+                                .weak(),
+                            );
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.button("📋").on_hover_text("Copy to clipboard").clicked() {
+                                    ui.ctx().copy_text(preview.clone());
+                                }
+                            });
+                        });
+
+                        egui::ScrollArea::vertical()
+                            .id_salt("preview_scroll")
+                            .show(ui, |ui| {
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut preview.as_str())
+                                        .code_editor()
+                                        .desired_width(f32::INFINITY),
+                                );
+                            });
+                    }
+                    RequestTab::Schema => {
+                        let line_height = ui.text_style_height(&egui::TextStyle::Monospace);
+                        let rows = (ui.available_height() / line_height).max(1.0) as usize;
+
+                        ui.expand_to_include_rect(ui.max_rect());
+
+                        egui::ScrollArea::vertical()
+                            .id_salt("schema_scroll")
+                            .show(ui, |ui| {
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut self.request.response_schema)
+                                        .code_editor()
+                                        .hint_text("{ \"type\": \"object\", \"required\": [\"id\"] }")
+                                        .desired_width(f32::INFINITY)
+                                        .desired_rows(rows)
+                                        .layouter(&mut |ui, text, wrap_width| {
+                                            let mut job = MyApp::memoized_highlight_json(
+                                                &self.highlight_cache,
+                                                text.as_str(),
+                                                None,
+                                                None,
+                                            );
+                                            job.wrap.max_width = wrap_width;
+                                            ui.fonts_mut(|f| f.layout_job(job))
+                                        }),
+                                );
+                            });
                     }
                     RequestTab::Auth => {
                         ui.horizontal(|ui| {
                             ui.label("Type:");
                             egui::ComboBox::from_id_salt("auth_type")
-                                .selected_text(if self.auth_type == AuthType::None {
+                                .selected_text(if self.request.auth_type == AuthType::None {
                                     "No Auth"
                                 } else {
                                     "Bearer Token"
                                 })
                                 .show_ui(ui, |ui| {
                                     ui.selectable_value(
-                                        &mut self.auth_type,
+                                        &mut self.request.auth_type,
                                         AuthType::None,
                                         "No Auth",
                                     );
                                     ui.selectable_value(
-                                        &mut self.auth_type,
+                                        &mut self.request.auth_type,
                                         AuthType::Bearer,
                                         "Bearer Token",
                                     );
                                 });
                         });
 
-                        if self.auth_type == AuthType::Bearer {
+                        if self.request.auth_type == AuthType::Bearer {
                             ui.add_space(6.0);
                             ui.horizontal(|ui| {
                                 ui.label(egui::RichText::new("Token:").size(18.0));
                                 ui.add_sized(
                                     ui.available_size(),
-                                    egui::TextEdit::singleline(&mut self.bearer_token)
+                                    egui::TextEdit::singleline(&mut self.request.bearer_token)
                                         .min_size(egui::vec2(0.0, 30.0))
                                         .vertical_align(egui::Align::Center),
                                 );
                             });
                         }
                     }
+                    RequestTab::Params => {
+                        egui::ScrollArea::vertical()
+                            .id_salt("params_scroll")
+                            .auto_shrink([false; 2])
+                            .show(ui, |ui| {
+                                let mut to_remove = None;
+
+                                for (i, param) in self.request.params.iter_mut().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        ui.checkbox(&mut param.enabled, "")
+                                            .on_hover_text("Send this parameter");
+                                        ui.add(
+                                            egui::TextEdit::singleline(&mut param.name)
+                                                .hint_text("name")
+                                                .desired_width(ui.available_width() * 0.3),
+                                        );
+                                        ui.add(
+                                            egui::TextEdit::singleline(&mut param.value)
+                                                .hint_text("value")
+                                                .desired_width(ui.available_width() - 40.0),
+                                        );
+                                        if ui.button("❌").clicked() {
+                                            to_remove = Some(i);
+                                        }
+                                    });
+                                }
+
+                                if let Some(i) = to_remove {
+                                    self.request.params.remove(i);
+                                }
+
+                                ui.add_space(6.0);
+                                ui.horizontal(|ui| {
+                                    if ui.button("➕ Add Param").clicked() {
+                                        self.request.params.push(QueryParam::default());
+                                    }
+                                    if ui
+                                        .button("⬇ From URL")
+                                        .on_hover_text("Move the URL's query string into params")
+                                        .clicked()
+                                    {
+                                        self.split_url_query();
+                                    }
+                                });
+                                if !self.request.params.is_empty() {
+                                    ui.add_space(6.0);
+                                    ui.label(
+                                        egui::RichText::new(self.request.full_url())
+                                            .monospace()
+                                            .weak(),
+                                    );
+                                }
+                            });
+                    }
+                    RequestTab::Cookies => {
+                        egui::ScrollArea::vertical()
+                            .id_salt("cookies_scroll")
+                            .auto_shrink([false; 2])
+                            .show(ui, |ui| {
+                                let mut to_remove = None;
+
+                                for (i, cookie) in self.request.cookies.iter_mut().enumerate() {
+                                    ui.horizontal(|ui| {
+                                        ui.checkbox(&mut cookie.enabled, "")
+                                            .on_hover_text("Send this cookie");
+                                        ui.add(
+                                            egui::TextEdit::singleline(&mut cookie.name)
+                                                .hint_text("name")
+                                                .desired_width(ui.available_width() * 0.3),
+                                        );
+                                        ui.add(
+                                            egui::TextEdit::singleline(&mut cookie.value)
+                                                .hint_text("value")
+                                                .desired_width(ui.available_width() - 40.0),
+                                        );
+                                        if ui.button("❌").clicked() {
+                                            to_remove = Some(i);
+                                        }
+                                    });
+                                }
+
+                                if let Some(i) = to_remove {
+                                    self.request.cookies.remove(i);
+                                }
+
+                                ui.add_space(6.0);
+                                if ui.button("➕ Add Cookie").clicked() {
+                                    self.request.cookies.push(CookieRow::default());
+                                }
+                            });
+                    }
                 }
             });
     }
 
     fn render_response_section(&mut self, ui: &mut egui::Ui) {
         // Reset after 1.5 seconds
-        if let Some(t) = self.copied_at {
-            if t.elapsed() > std::time::Duration::from_secs_f32(1.5) {
-                self.copied_at = None;
-                self.copied = false;
-            }
+        if let Some(t) = self.copied_at
+            && t.elapsed() > std::time::Duration::from_secs_f32(1.5)
+        {
+            self.copied_at = None;
+            self.copied = false;
         }
         egui::Frame::NONE
             .stroke(egui::Stroke::new(1.0, egui::Color32::from_gray(60)))
@@ -659,10 +2107,79 @@ impl MyApp {
                             ui.spinner();
                         }
                         if !self.response_status.is_empty() {
-                            ui.label(&self.response_status);
+                            match (self.expected_status_code(), self.response_status_code()) {
+                                (Some(expected), Some(actual)) if expected != actual => {
+                                    let warn = egui::Color32::from_rgb(255, 165, 0);
+                                    ui.colored_label(warn, &self.response_status);
+                                    ui.colored_label(
+                                        warn,
+                                        format!("expected {}, got {}", expected, actual),
+                                    );
+                                }
+                                _ => {
+                                    ui.label(&self.response_status);
+                                }
+                            }
+                        }
+                        if let Some(result) = self.baseline_comparison() {
+                            match result {
+                                Ok(()) => {
+                                    ui.colored_label(
+                                        egui::Color32::from_rgb(100, 200, 100),
+                                        "= Baseline",
+                                    )
+                                    .on_hover_text("Status and body match the pinned baseline");
+                                }
+                                Err(difference) => {
+                                    ui.colored_label(
+                                        egui::Color32::from_rgb(255, 165, 0),
+                                        "≠ Baseline",
+                                    )
+                                    .on_hover_text(difference);
+                                }
+                            }
+                        }
+                        if let Some(issue) = &self.sent_body_issue {
+                            ui.colored_label(egui::Color32::from_rgb(255, 165, 0), "⚠ Body")
+                                .on_hover_text(format!(
+                                    "The request body {}; servers often reject this",
+                                    issue
+                                ));
+                        }
+                        match &self.schema_violations {
+                            Some(violations) if violations.is_empty() => {
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(100, 200, 100),
+                                    "✔ Schema",
+                                )
+                                .on_hover_text("Response matches the schema");
+                            }
+                            Some(violations) => {
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(200, 100, 100),
+                                    format!("✖ Schema ({})", violations.len()),
+                                )
+                                .on_hover_text(violations.join("\n"));
+                            }
+                            None => {}
                         }
                     });
                 });
+
+                if let Some(violations) = &self.schema_violations
+                    && !violations.is_empty()
+                {
+                    egui::CollapsingHeader::new(format!(
+                        "Schema violations ({})",
+                        violations.len()
+                    ))
+                    .id_salt("schema_violations")
+                    .show(ui, |ui| {
+                        for violation in violations {
+                            ui.colored_label(egui::Color32::from_rgb(200, 100, 100), violation);
+                        }
+                    });
+                }
                 ui.add_space(6.0);
 
                 ui.horizontal(|ui| {
@@ -672,8 +2189,121 @@ impl MyApp {
                         ResponseTab::Headers,
                         "Headers",
                     );
+                    if !self.response_trailers.is_empty() {
+                        ui.selectable_value(
+                            &mut self.active_response_tab,
+                            ResponseTab::Trailers,
+                            "Trailers",
+                        )
+                        .on_hover_text("Trailer fields sent after the body");
+                    }
+                    if !self.response_paths.is_empty() {
+                        ui.selectable_value(
+                            &mut self.active_response_tab,
+                            ResponseTab::Paths,
+                            "Paths",
+                        )
+                        .on_hover_text("JSON flattened to path = value lines");
+                    }
+                    if self.response_json.is_some() {
+                        ui.selectable_value(
+                            &mut self.active_response_tab,
+                            ResponseTab::Tree,
+                            "Tree",
+                        )
+                        .on_hover_text("Browse and search the JSON as a tree");
+                    }
 
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        let mut pinned = self.baseline.is_some();
+                        if ui
+                            .add_enabled(
+                                pinned || (!self.loading && self.last_request.is_some()),
+                                egui::Button::new("📌").selected(pinned),
+                            )
+                            .on_hover_text(if pinned {
+                                "Unpin the baseline response"
+                            } else {
+                                "Pin this response as a baseline to compare later ones against"
+                            })
+                            .clicked()
+                        {
+                            pinned = !pinned;
+                            self.baseline = if pinned {
+                                self.current_baseline()
+                            } else {
+                                None
+                            };
+                        }
+
+                        if ui
+                            .add_enabled(
+                                !self.loading && !self.response_status.is_empty(),
+                                egui::Button::new("📦"),
+                            )
+                            .on_hover_text("Save bundle: body, headers, timing and request")
+                            .clicked()
+                            && let Some(parent) = rfd::FileDialog::new().pick_folder()
+                        {
+                            match self.save_response_bundle(&parent) {
+                                Ok(dir) => {
+                                    self.bundle_error = None;
+                                    self.log(
+                                        LogLevel::Info,
+                                        format!("Saved response bundle to {}", dir.display()),
+                                    );
+                                    let _ = opener::open(&dir);
+                                }
+                                Err(e) => {
+                                    self.log(LogLevel::Error, format!("Bundle failed: {}", e));
+                                    self.bundle_error = Some(e.to_string());
+                                }
+                            }
+                        }
+                        if let Some(err) = &self.bundle_error {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(200, 100, 100),
+                                format!("Bundle failed: {}", err),
+                            );
+                        }
+
+                        if self.active_response_tab == ResponseTab::Body
+                            && let Some(format) = self.response_format()
+                        {
+                            let pretty = self.view_pretty(format);
+                            if ui
+                                .selectable_label(pretty, "{ } Pretty")
+                                .on_hover_text(format!(
+                                    "Pretty print {} responses; remembered for this format",
+                                    format.to_uppercase()
+                                ))
+                                .clicked()
+                            {
+                                self.settings
+                                    .pretty_by_format
+                                    .insert(format.to_string(), !pretty);
+                                self.apply_body_view();
+                            }
+                        }
+
+                        if self.active_response_tab == ResponseTab::Body
+                            && !self.is_response_binary
+                            && !self.response_raw_body.is_empty()
+                        {
+                            let rendered = self.shows_markdown();
+                            if ui
+                                .selectable_label(rendered, "Ⓜ Markdown")
+                                .on_hover_text(if rendered {
+                                    "Showing rendered Markdown. Click for the raw text"
+                                } else {
+                                    "Render the body as Markdown"
+                                })
+                                .clicked()
+                            {
+                                self.markdown_override = Some(!rendered);
+                            }
+                        }
+
                         let label = if self.copied { "✅" } else { "📋" };
                         let tooltip = if self.copied {
                             "Copied!"
@@ -684,8 +2314,10 @@ impl MyApp {
                         if ui.button(label).on_hover_text(tooltip).clicked() {
                             // Copy the active text to clipboard
                             let text_to_copy = match self.active_response_tab {
-                                ResponseTab::Body => &self.response_body,
                                 ResponseTab::Headers => &self.response_headers,
+                                ResponseTab::Trailers => &self.response_trailers,
+                                ResponseTab::Paths => &self.response_paths,
+                                ResponseTab::Body | ResponseTab::Tree => &self.response_body,
                                 ResponseTab::None => "",
                             };
 
@@ -695,11 +2327,93 @@ impl MyApp {
                             self.copied = true;
                             self.copied_at = Some(std::time::Instant::now());
                         }
+
+                        if self.response_content_type.starts_with("text/html") {
+                            if ui.button("🌐").on_hover_text("Open in browser").clicked() {
+                                self.open_response_in_browser();
+                            }
+                        } else if !self.response_location.is_empty()
+                            && ui
+                                .button("🌐")
+                                .on_hover_text(format!("Open {}", self.response_location))
+                                .clicked()
+                        {
+                            let _ = opener::open_browser(&self.response_location);
+                        }
                     });
                 });
                 ui.separator();
                 ui.add_space(4.0);
 
+                if self.active_response_tab == ResponseTab::Tree {
+                    self.render_json_tree(ui);
+                    return;
+                }
+
+                if self.active_response_tab == ResponseTab::Body && self.body_collapsed() {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(255, 165, 0),
+                            format!(
+                                "Showing the first {} of {}",
+                                format_size(COLLAPSED_BODY_PREVIEW),
+                                format_size(self.response_body.len())
+                            ),
+                        );
+                        if ui
+                            .button(format!(
+                                "Show full ({})",
+                                format_size(self.response_body.len())
+                            ))
+                            .clicked()
+                        {
+                            self.show_full_body = true;
+                        }
+                    });
+                    ui.add_space(4.0);
+                }
+
+                if self.active_response_tab == ResponseTab::Body
+                    && let Some(echo) = &self.trace_echo
+                {
+                    let warn = egui::Color32::from_rgb(255, 165, 0);
+                    egui::CollapsingHeader::new("Echoed request")
+                        .id_salt("trace_echo")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            ui.label(egui::RichText::new(&echo.start_line).monospace().strong());
+                            egui::Grid::new("trace_echo_headers")
+                                .num_columns(3)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for (name, value, change) in &echo.headers {
+                                        ui.monospace(name);
+                                        ui.monospace(value);
+                                        match change {
+                                            EchoedHeader::Unchanged => ui.label(""),
+                                            EchoedHeader::Added => {
+                                                ui.colored_label(warn, "➕ added").on_hover_text(
+                                                    "Not among the headers sent; likely added by \
+                                                     a proxy or load balancer on the way",
+                                                )
+                                            }
+                                            EchoedHeader::Changed(sent) => ui
+                                                .colored_label(warn, "✎ changed")
+                                                .on_hover_text(format!("Sent as: {}", sent)),
+                                        };
+                                        ui.end_row();
+                                    }
+                                });
+                            if !echo.removed.is_empty() {
+                                ui.colored_label(
+                                    egui::Color32::from_rgb(200, 100, 100),
+                                    format!("➖ Removed on the way: {}", echo.removed.join(", ")),
+                                );
+                            }
+                        });
+                    ui.add_space(4.0);
+                }
+
                 let should_scroll = self.find_dialog.target_scroll_y.take();
                 let scroll_response = egui::ScrollArea::vertical().id_salt("response_scroll");
 
@@ -728,25 +2442,35 @@ impl MyApp {
                                 ui.label(&self.response_body);
                                 ui.add_space(8.0);
 
-                                if ui.button("💾 Save and Open").clicked() {
-                                    if let Some(path) = rfd::FileDialog::new()
+                                if ui.button("💾 Save and Open").clicked()
+                                    && let Some(path) = rfd::FileDialog::new()
                                         .set_file_name(&self.response_filename)
                                         .save_file()
-                                    {
-                                        if std::fs::write(&path, &self.response_bytes).is_ok() {
-                                            let _ = opener::open(&path);
-                                        }
-                                    }
+                                    && std::fs::write(&path, &self.response_bytes).is_ok()
+                                {
+                                    let _ = opener::open(&path);
                                 }
                             }
                         }
                         return;
                     }
 
+                    if self.active_response_tab == ResponseTab::Body && self.shows_markdown() {
+                        let shown = &self.response_body[..self.shown_body_len()];
+                        egui_commonmark::CommonMarkViewer::new().show(
+                            ui,
+                            &mut self.markdown_cache,
+                            &markdown_images_as_links(shown),
+                        );
+                        return;
+                    }
+
                     let text = match self.active_response_tab {
                         ResponseTab::Body => &self.response_body,
                         ResponseTab::Headers => &self.response_headers,
-                        ResponseTab::None => return,
+                        ResponseTab::Trailers => &self.response_trailers,
+                        ResponseTab::Paths => &self.response_paths,
+                        ResponseTab::Tree | ResponseTab::None => return,
                     };
 
                     if ui.memory(|mem| mem.focused().is_some()) {
@@ -758,43 +2482,116 @@ impl MyApp {
 
                     ui.expand_to_include_rect(ui.max_rect());
 
-                    let text_str = text.as_str();
+                    let text_str = if self.active_response_tab == ResponseTab::Body {
+                        &text[..self.shown_body_len()]
+                    } else {
+                        text.as_str()
+                    };
 
                     let mut layouter =
                         |ui: &egui::Ui, buffer_text: &dyn egui::TextBuffer, wrap_width: f32| {
-                            let job = if self.find_dialog.open
-                                && self.find_dialog.context == FindContext::ResponseBody
-                                && !self.find_dialog.find_text.is_empty()
-                            {
-                                MyApp::memoized_highlight_json(
-                                    &self.highlight_cache,
-                                    buffer_text.as_str(),
-                                    &self.find_dialog.find_text,
-                                    self.find_dialog.current_match_pos,
-                                    self.find_dialog.case_sensitive,
-                                )
-                            } else {
-                                MyApp::memoized_highlight_json(
-                                    &self.highlight_cache,
-                                    buffer_text.as_str(),
-                                    "",
-                                    None,
-                                    false,
-                                )
-                            };
-                            let mut job = job;
+                            let search = (self.find_dialog.open
+                                && self.find_dialog.context == FindContext::ResponseBody)
+                                .then(|| self.find_dialog.matcher())
+                                .flatten();
+                            let mut job = MyApp::memoized_highlight_json(
+                                &self.highlight_cache,
+                                buffer_text.as_str(),
+                                search.as_ref(),
+                                self.find_dialog.current_match_pos,
+                            );
                             job.wrap.max_width = wrap_width;
                             ui.fonts_mut(|f| f.layout_job(job))
                         };
 
-                    ui.add(
-                        egui::TextEdit::multiline(&mut &*text_str)
-                            .code_editor()
-                            .desired_width(f32::INFINITY)
-                            .cursor_at_end(false)
-                            .desired_rows(rows)
-                            .layouter(&mut layouter),
-                    );
+                    let output = egui::TextEdit::multiline(&mut &*text_str)
+                        .code_editor()
+                        .desired_width(f32::INFINITY)
+                        .cursor_at_end(false)
+                        .desired_rows(rows)
+                        .layouter(&mut layouter)
+                        .show(ui);
+
+                    // Copy the selection, or everything, as a string literal
+                    output.response.context_menu(|ui| {
+                        let selected = output
+                            .state
+                            .cursor
+                            .char_range()
+                            .filter(|range| !range.is_empty())
+                            .map(|range| {
+                                let chars = range.as_sorted_char_range();
+                                let byte = |i: usize| {
+                                    text_str
+                                        .char_indices()
+                                        .nth(i)
+                                        .map_or(text_str.len(), |(b, _)| b)
+                                };
+                                &text_str[byte(chars.start)..byte(chars.end)]
+                            });
+                        ui.label(
+                            egui::RichText::new(match selected {
+                                Some(s) => format!("Selection ({} chars)", s.chars().count()),
+                                None => "Whole text".to_string(),
+                            })
+                            .weak(),
+                        );
+                        // The whole body, not just a collapsed preview of it
+                        let source = selected.unwrap_or(text);
+                        for language in [
+                            EscapeLanguage::Json,
+                            EscapeLanguage::Rust,
+                            EscapeLanguage::JavaScript,
+                        ] {
+                            if ui
+                                .button(format!("Copy as {} string", language.name()))
+                                .clicked()
+                            {
+                                ui.ctx().copy_text(escape_string_literal(source, language));
+                                ui.close();
+                            }
+                        }
+                    });
+
+                    // Keep the Body cursor and the tree selection on the same node
+                    if self.active_response_tab == ResponseTab::Body
+                        && self.settings.sync_json_views
+                        && !self.json_line_paths.is_empty()
+                    {
+                        if let Some(line) = self.body_scroll_line.take() {
+                            let start: usize = text_str
+                                .split('\n')
+                                .take(line)
+                                .map(|l| l.chars().count() + 1)
+                                .sum();
+                            let cursor = egui::text::CCursor::new(start);
+                            let rect = output
+                                .galley
+                                .pos_from_cursor(cursor)
+                                .translate(output.galley_pos.to_vec2());
+                            ui.scroll_to_rect(rect, Some(egui::Align::Center));
+                            let mut state = output.state;
+                            state
+                                .cursor
+                                .set_char_range(Some(egui::text::CCursorRange::one(cursor)));
+                            state.store(ui.ctx(), output.response.id);
+                            self.body_cursor = Some(start);
+                        } else if output.response.has_focus()
+                            && let Some(range) = output.cursor_range
+                            && self.body_cursor != Some(range.primary.index)
+                        {
+                            self.body_cursor = Some(range.primary.index);
+                            let line = text_str
+                                .chars()
+                                .take(range.primary.index)
+                                .filter(|&c| c == '\n')
+                                .count();
+                            if let Some(path) = self.json_line_paths.get(line) {
+                                self.selected_json_path = Some(path.clone());
+                                self.reveal_tree_selection = true;
+                            }
+                        }
+                    }
 
                     if let Some(target_y) = should_scroll {
                         // Get current scroll position and calculate delta
@@ -806,393 +2603,2504 @@ impl MyApp {
             });
     }
 
-    // Update send_request function
-    fn send_request(&mut self) {
-        self.loading = true;
-        self.response_body = "Loading...".to_string();
-        self.response_status = String::new();
+    /// Length of the part of the response body on screen: all of it, or the
+    /// preview while collapsed.
+    fn shown_body_len(&self) -> usize {
+        if !self.body_collapsed() {
+            return self.response_body.len();
+        }
+        let mut end = COLLAPSED_BODY_PREVIEW;
+        while !self.response_body.is_char_boundary(end) {
+            end -= 1;
+        }
+        end
+    }
 
-        // Reset cancel flag and start timer
-        self.cancel_flag.store(false, Ordering::Relaxed);
-        self.request_start_time = Some(std::time::Instant::now());
+    /// Whether only the start of a large text body is being shown.
+    fn body_collapsed(&self) -> bool {
+        !self.show_full_body
+            && !self.is_response_binary
+            && self.response_body.len() > COLLAPSED_BODY_PREVIEW
+            && self
+                .settings
+                .collapse_body_over_kb
+                .is_some_and(|kb| self.response_body.len() > kb * 1024)
+    }
 
-        let url = self.url.clone();
-        let method = self.method.clone();
-        let body = self.body.clone();
-        let mut headers = self.parse_headers();
-        let auth_type = self.auth_type.clone();
-        let bearer_token = self.bearer_token.clone();
-        let content_type = self.content_type.clone();
-        let form_data = self.form_data.clone();
-        let tx = self.tx.clone();
-        let cancel_flag = self.cancel_flag.clone();
-        let timeout = self.request_timeout;
+    /// Whether the body is rendered as Markdown: `text/markdown` responses are
+    /// unless overridden, anything else only when forced.
+    fn shows_markdown(&self) -> bool {
+        if self.is_response_binary || self.response_raw_body.is_empty() {
+            return false;
+        }
+        self.markdown_override.unwrap_or_else(|| {
+            let essence = self
+                .response_content_type
+                .split(';')
+                .next()
+                .unwrap_or_default();
+            matches!(
+                essence.trim().to_ascii_lowercase().as_str(),
+                "text/markdown" | "text/x-markdown"
+            )
+        })
+    }
 
-        // Add Bearer token to headers if set
-        if auth_type == AuthType::Bearer && !bearer_token.is_empty() {
-            if let Ok(header_value) =
-                reqwest::header::HeaderValue::from_str(&format!("Bearer {}", bearer_token))
-            {
-                headers.insert(reqwest::header::AUTHORIZATION, header_value);
-            }
+    /// Format family of the current text response, if it has a pretty view.
+    fn response_format(&self) -> Option<&'static str> {
+        if self.is_response_binary || self.response_raw_body.is_empty() {
+            return None;
         }
+        body_format(&self.response_content_type, &self.response_raw_body)
+    }
 
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            let response = rt.block_on(async {
-                // Check if cancelled before starting
-                if cancel_flag.load(Ordering::Relaxed) {
-                    return HttpResponse {
+    /// Remembered view for `format`; JSON defaults to pretty, XML to raw.
+    fn view_pretty(&self, format: &str) -> bool {
+        self.settings
+            .pretty_by_format
+            .get(format)
+            .copied()
+            .unwrap_or(format == "json")
+    }
+
+    /// Re-renders the response body from the raw text in the remembered view.
+    fn apply_body_view(&mut self) {
+        let Some(format) = self.response_format() else {
+            return;
+        };
+        let started = std::time::Instant::now();
+        let raw = &self.response_raw_body;
+        self.response_body = match (format, self.view_pretty(format)) {
+            ("json", true) => pretty_json(raw.clone()),
+            ("xml", true) => pretty_xml(raw),
+            _ => raw.clone(),
+        };
+        if let Some(metrics) = &mut self.response_metrics {
+            metrics.format_time = Some(started.elapsed());
+        }
+        if self.find_dialog.open && !self.find_dialog.find_text.is_empty() {
+            self.refresh_find_results();
+        }
+        self.index_json_lines();
+    }
+
+    /// Maps Body lines to tree nodes when the Body is the pretty-printed JSON,
+    /// whose layout the tree can reproduce.
+    fn index_json_lines(&mut self) {
+        self.json_line_paths.clear();
+        if self.response_format() != Some("json") || !self.view_pretty("json") {
+            return;
+        }
+        if let Some(json) = &self.response_json {
+            json_line_paths(json, "", &mut self.json_line_paths);
+            if self.json_line_paths.len() != self.response_body.lines().count() {
+                self.json_line_paths.clear();
+            }
+        }
+    }
+
+    fn render_json_tree(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("🔍");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.tree_search)
+                    .hint_text("Search keys and values")
+                    .desired_width(240.0),
+            );
+            if let Some(matches) = &self.tree_matches
+                && !matches.query.is_empty()
+            {
+                let count = matches.matched.len();
+                let color = if count > 0 {
+                    egui::Color32::from_rgb(100, 200, 100)
+                } else {
+                    egui::Color32::from_rgb(200, 100, 100)
+                };
+                ui.colored_label(color, format!("{} matching nodes", count));
+            }
+        });
+        ui.add_space(4.0);
+
+        let Some(json) = &self.response_json else {
+            return;
+        };
+        let query = self.tree_search.trim().to_lowercase();
+        if self
+            .tree_matches
+            .as_ref()
+            .is_none_or(|matches| matches.query != query)
+        {
+            let mut matches = TreeMatches {
+                query: query.clone(),
+                ..Default::default()
+            };
+            if !query.is_empty() {
+                search_json_tree(json, "", None, &query, &mut matches);
+            }
+            self.tree_matches = Some(matches);
+        }
+        let Some(matches) = &self.tree_matches else {
+            return;
+        };
+
+        let mut selection = TreeSelection {
+            selected: self.selected_json_path.as_deref(),
+            reveal: self.reveal_tree_selection,
+            clicked: None,
+        };
+        egui::ScrollArea::vertical()
+            .id_salt("tree_scroll")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                render_json_node(ui, "root", json, "", matches, &mut selection);
+            });
+        let clicked = selection.clicked;
+
+        self.reveal_tree_selection = false;
+        if let Some((path, jump)) = clicked {
+            if self.settings.sync_json_views
+                && let Some(line) = self.json_line_paths.iter().position(|p| *p == path)
+            {
+                self.body_scroll_line = Some(line);
+                if jump {
+                    self.active_response_tab = ResponseTab::Body;
+                }
+            }
+            self.selected_json_path = Some(path);
+        }
+    }
+
+    // Update send_request function
+    fn send_request(&mut self) {
+        if self.dry_run {
+            self.dry_run_request();
+            return;
+        }
+        self.loading = true;
+        // Polling updates the last response in place
+        if !self.polling.running() {
+            self.response_body = "Loading...".to_string();
+            self.response_status = String::new();
+        }
+        self.schema_violations = None;
+        self.sent_body_issue = (self.request.method.has_body()
+            && self.request.content_type.is_raw())
+        .then(|| body_encoding_issue(&self.request.body))
+        .flatten();
+
+        // Reset cancel flag and start timer
+        self.cancel_flag.store(false, Ordering::Relaxed);
+        self.request_start_time = Some(std::time::Instant::now());
+
+        let spec = self.prepared_request();
+        self.log(
+            LogLevel::Info,
+            format!("→ {:?} {}", spec.method, spec.url.trim()),
+        );
+        if let Some(issue) = &self.sent_body_issue {
+            self.log(LogLevel::Warn, format!("Request body {}", issue));
+        }
+        self.pending_history = Some(self.request.clone());
+        self.last_request = Some(spec.clone());
+        self.last_sent_at = Some(std::time::SystemTime::now());
+        let client = self.client.clone();
+        let cancel_flag = self.cancel_flag.clone();
+        let tx = self.tx.clone();
+
+        self.runtime.spawn(async move {
+            let response = client::execute_with(&client, spec, &cancel_flag).await;
+            let _ = tx.send(response);
+        });
+    }
+
+    /// Builds the request as `send_request` would and shows it in the Preview
+    /// tab along with any warnings, without sending anything.
+    fn dry_run_request(&mut self) {
+        let spec = self.prepared_request();
+        let mut warnings = Vec::new();
+        if spec.method.has_body()
+            && spec.content_type.is_raw()
+            && let Some(issue) = body_encoding_issue(&spec.body)
+        {
+            warnings.push(format!("Request body {}", issue));
+        }
+        for skipped in parse_header_lines_with(&spec.headers, spec.strict_headers).skipped {
+            warnings.push(format!(
+                "Header line {} ignored: {}",
+                skipped.line, skipped.reason
+            ));
+        }
+        let built = client::build_without_sending(&self.client, spec.clone());
+
+        let target = format!("{:?} {}", spec.method, spec.full_url().trim());
+        self.log(LogLevel::Info, format!("Dry run: {} (not sent)", target));
+        for warning in &warnings {
+            self.log(LogLevel::Warn, format!("Dry run: {}", warning));
+        }
+        if let Err(e) = &built {
+            self.log(
+                LogLevel::Error,
+                format!("Dry run: request can't be built: {}", e),
+            );
+        }
+
+        self.request_preview = Some((spec.clone(), client::preview_request(&spec)));
+        self.active_request_tab = RequestTab::Preview;
+        self.dry_run_result = Some(DryRun {
+            target,
+            built,
+            warnings,
+        });
+    }
+
+    fn render_dry_run(&mut self, ui: &mut egui::Ui) {
+        if !self.dry_run {
+            return;
+        }
+        let ok = egui::Color32::from_rgb(100, 200, 100);
+        let warn = egui::Color32::from_rgb(255, 165, 0);
+        let error = egui::Color32::from_rgb(200, 100, 100);
+
+        ui.horizontal(|ui| {
+            ui.colored_label(warn, egui::RichText::new("🧪 DRY RUN — not sent").strong())
+                .on_hover_text(
+                    "Send builds the request and shows it in Preview without sending it",
+                );
+            let Some(result) = &self.dry_run_result else {
+                return;
+            };
+            ui.separator();
+            match &result.built {
+                Ok(size) => {
+                    let body = size.map(|s| format!(", body {}", format_size(s)));
+                    ui.colored_label(
+                        ok,
+                        format!("✔ {}{}", result.target, body.unwrap_or_default()),
+                    );
+                }
+                Err(e) => {
+                    ui.colored_label(error, format!("✖ Can't build request: {}", e));
+                }
+            }
+            if !result.warnings.is_empty() {
+                ui.separator();
+                ui.colored_label(warn, format!("⚠ {} warnings", result.warnings.len()))
+                    .on_hover_text(result.warnings.join("\n"));
+            }
+        });
+    }
+
+    /// The request as it will go out: the editor's spec plus host profile and
+    /// settings defaults.
+    fn prepared_request(&self) -> RequestSpec {
+        let mut spec = self.request.clone();
+        spec.strict_headers = self.settings.strict_header_trim;
+
+        let variables = self.environment_variables();
+        if !variables.is_empty() {
+            spec.url = substitute_variables(&spec.url, &variables);
+            for param in &mut spec.params {
+                param.name = substitute_variables(&param.name, &variables);
+                param.value = substitute_variables(&param.value, &variables);
+            }
+            spec.headers = substitute_variables(&spec.headers, &variables);
+            spec.body = substitute_variables(&spec.body, &variables);
+            spec.bearer_token = substitute_variables(&spec.bearer_token, &variables);
+            for field in &mut spec.form_data {
+                field.key = substitute_variables(&field.key, &variables);
+                field.value = substitute_variables(&field.value, &variables);
+            }
+            for cookie in &mut spec.cookies {
+                cookie.value = substitute_variables(&cookie.value, &variables);
+            }
+        }
+
+        // Fill in anything the request leaves unset from the matching host profile
+        if let Some(profile) = self.matching_host_profile() {
+            let headers = spec.header_map();
+            let profile_headers =
+                parse_header_lines_with(&profile.headers, self.settings.strict_header_trim).headers;
+            for (name, value) in profile_headers.iter() {
+                if !headers.contains_key(name)
+                    && let Ok(value) = value.to_str()
+                {
+                    spec = spec.header(name.as_str(), value);
+                }
+            }
+
+            if spec.auth_type == AuthType::None
+                && profile.auth_type == AuthType::Bearer
+                && !profile.bearer_token.is_empty()
+                && !headers.contains_key(reqwest::header::AUTHORIZATION)
+            {
+                spec = spec.bearer(profile.bearer_token.clone());
+            }
+        }
+
+        if !self.settings.accept_encoding.is_empty()
+            && !spec
+                .header_map()
+                .contains_key(reqwest::header::ACCEPT_ENCODING)
+        {
+            spec = spec.header("Accept-Encoding", &self.settings.accept_encoding);
+        }
+
+        if !self.settings.accept_language.trim().is_empty()
+            && !spec
+                .header_map()
+                .contains_key(reqwest::header::ACCEPT_LANGUAGE)
+        {
+            spec = spec.header("Accept-Language", self.settings.accept_language.trim());
+        }
+
+        spec
+    }
+
+    /// Sends the current request `repeat.count` times, with at most
+    /// `max_concurrent_requests` in flight at once.
+    fn start_repeat_run(&mut self) {
+        if self.dry_run {
+            return;
+        }
+        let total = self.repeat.count.max(1);
+        self.repeat = RepeatRun {
+            count: self.repeat.count,
+            total,
+            ..RepeatRun::default()
+        };
+        // Drop results still queued from an earlier, stopped run
+        while self.repeat_rx.try_recv().is_ok() {}
+
+        let spec = self.prepared_request();
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Repeat run: {} × {:?} {}, {} at a time",
+                total,
+                spec.method,
+                spec.url.trim(),
+                self.settings.max_concurrent_requests.max(1)
+            ),
+        );
+        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(
+            self.settings.max_concurrent_requests.max(1),
+        ));
+
+        for _ in 0..total {
+            let client = self.client.clone();
+            let spec = spec.clone();
+            let semaphore = semaphore.clone();
+            let cancel_flag = self.repeat.cancel_flag.clone();
+            let tx = self.repeat_tx.clone();
+
+            self.runtime.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let response = if cancel_flag.load(Ordering::Relaxed) {
+                    HttpResponse {
                         status: "Cancelled".to_string(),
-                        headers: String::new(),
-                        body: "Request was cancelled".to_string(),
-                        is_binary: false,
-                        filename: String::new(),
-                        bytes: Vec::new(),
-                        content_type: String::new(),
-                    };
+                        ..Default::default()
+                    }
+                } else {
+                    client::execute_with(&client, spec, &cancel_flag).await
+                };
+                let _ = tx.send(response);
+            });
+        }
+    }
+
+    fn log(&mut self, level: LogLevel, message: impl Into<String>) {
+        if self.log.len() >= MAX_LOG_ENTRIES {
+            self.log.pop_front();
+        }
+        self.log.push_back(LogEntry {
+            time: log_timestamp(),
+            level,
+            message: message.into(),
+        });
+    }
+
+    /// Moves the URL's query string into the Params editor.
+    fn split_url_query(&mut self) {
+        if let Some((base, params)) = client::split_query(&self.request.url) {
+            self.request.url = base;
+            self.request.params.extend(params);
+            self.active_request_tab = RequestTab::Params;
+        }
+    }
+
+    /// Distinct URL bases from the history, most recent first.
+    fn recent_base_urls(&self) -> Vec<String> {
+        let mut bases: Vec<String> = Vec::new();
+        for entry in &self.history {
+            let (base, _) = client::split_base_url(entry.request.url.trim());
+            if !base.is_empty() && !bases.iter().any(|b| b == base) {
+                bases.push(base.to_string());
+                if bases.len() == MAX_RECENT_BASES {
+                    break;
+                }
+            }
+        }
+        bases
+    }
+
+    /// Draws the URL as a base field with a recent hosts menu and a path
+    /// field, writing edits back to the URL. Returns the path field.
+    fn render_split_url(&mut self, ui: &mut egui::Ui) -> egui::Response {
+        if format!("{}{}", self.url_halves.0, self.url_halves.1) != self.request.url {
+            let (base, path) = client::split_base_url(&self.request.url);
+            self.url_halves = (base.to_string(), path.to_string());
+        }
+        let enabled = !self.request_locked;
+        let font = egui::FontId::proportional(18.0);
+
+        // Laid out right to left: path, then the hosts menu, then the base
+        let path_width = (ui.available_width() * 0.6).max(120.0);
+        let path = ui.add_enabled(
+            enabled,
+            egui::TextEdit::singleline(&mut self.url_halves.1)
+                .desired_width(path_width)
+                .min_size(egui::vec2(0.0, 30.0))
+                .hint_text(egui::RichText::new("/endpoint").size(18.0))
+                .vertical_align(egui::Align::Center)
+                .font(font.clone()),
+        );
+        let mut picked = false;
+        ui.add_enabled_ui(enabled, |ui| {
+            ui.menu_button("▾", |ui| {
+                let bases = self.recent_base_urls();
+                if bases.is_empty() {
+                    ui.label(egui::RichText::new("No hosts in history yet").weak());
                 }
+                for base in bases {
+                    if ui
+                        .selectable_label(self.url_halves.0 == base, &base)
+                        .clicked()
+                    {
+                        self.url_halves.0 = base;
+                        picked = true;
+                        ui.close();
+                    }
+                }
+            })
+            .response
+            .on_hover_text("Recent hosts");
+        });
+        let base = ui.add_enabled(
+            enabled,
+            egui::TextEdit::singleline(&mut self.url_halves.0)
+                .desired_width(ui.available_width())
+                .min_size(egui::vec2(0.0, 30.0))
+                .hint_text(egui::RichText::new("https://api.example.com").size(18.0))
+                .vertical_align(egui::Align::Center)
+                .font(font),
+        );
+
+        if picked || base.changed() || path.changed() {
+            self.request.url = format!("{}{}", self.url_halves.0, self.url_halves.1);
+        }
+        path
+    }
+
+    /// Exchanges the active tab's request and response with those in `tab`.
+    fn swap_tab_state(&mut self, tab: &mut TabState) {
+        macro_rules! swap {
+            ($($field:ident),* $(,)?) => {
+                $(std::mem::swap(&mut self.$field, &mut tab.$field);)*
+            };
+        }
+        swap!(
+            request,
+            request_locked,
+            last_request,
+            last_sent_at,
+            body_file,
+            body_watcher,
+            body_watch_rx,
+            last_auto_send,
+            auto_send_pending,
+            response_status,
+            response_headers,
+            response_header_map,
+            response_trailers,
+            response_body,
+            response_raw_body,
+            markdown_override,
+            show_full_body,
+            is_response_binary,
+            response_filename,
+            response_bytes,
+            response_content_type,
+            response_location,
+            response_paths,
+            response_json,
+            tree_search,
+            tree_matches,
+            json_line_paths,
+            selected_json_path,
+            reveal_tree_selection,
+            body_scroll_line,
+            body_cursor,
+            response_metrics,
+            schema_violations,
+            sent_body_issue,
+            dry_run_result,
+            trace_echo,
+            baseline,
+            security_audit,
+            multipart_preview,
+            request_preview,
+        );
+    }
+
+    /// Makes tab `index` the active one. Refused while a request is in
+    /// flight, since its response belongs to the tab that sent it.
+    fn switch_request_tab(&mut self, index: usize) {
+        if self.loading || index == self.active_request_index || index >= self.request_tabs.len() {
+            return;
+        }
+        // Polling keeps sending whatever request is active
+        self.set_polling(false);
+        let mut tab = std::mem::take(&mut self.request_tabs[index]);
+        self.swap_tab_state(&mut tab);
+        self.request_tabs[self.active_request_index] = tab;
+        self.active_request_index = index;
+
+        self.find_dialog.current_match_pos = None;
+        if self.find_dialog.open {
+            self.refresh_find_results();
+        }
+    }
+
+    fn open_request_tab(&mut self, spec: RequestSpec) {
+        self.request_tabs.push(spec.into());
+        self.switch_request_tab(self.request_tabs.len() - 1);
+    }
+
+    /// Replaces the current request with `spec`, or opens it in a new tab
+    /// when the current one is locked. A new tab can't be shown while a
+    /// request is in flight, so that load is refused.
+    fn load_request(&mut self, spec: RequestSpec) {
+        if self.request_locked {
+            if self.loading {
+                self.log(
+                    LogLevel::Warn,
+                    "Request is locked; wait for the response before loading another",
+                );
+                return;
+            }
+            self.open_request_tab(spec);
+        } else {
+            self.request = spec;
+        }
+        self.active_request_tab = if self.request.method.has_body() {
+            RequestTab::Body
+        } else {
+            RequestTab::Headers
+        };
+    }
+
+    fn close_request_tab(&mut self, index: usize) {
+        if self.loading || self.request_tabs.len() < 2 || index >= self.request_tabs.len() {
+            return;
+        }
+        if index == self.active_request_index {
+            let next = if index == 0 { 1 } else { index - 1 };
+            self.switch_request_tab(next);
+        }
+        self.request_tabs.remove(index);
+        if index < self.active_request_index {
+            self.active_request_index -= 1;
+        }
+    }
+
+    /// Opens a copy of the current request in a new tab with another method.
+    fn duplicate_request_as(&mut self, method: HttpMethod) {
+        let mut spec = self.request.clone();
+        spec.method = method;
+        self.open_request_tab(spec);
+    }
+
+    fn render_request_tabs(&mut self, ui: &mut egui::Ui) {
+        let mut switch_to = None;
+        let mut close = None;
+        egui::ScrollArea::horizontal()
+            .id_salt("request_tabs")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    // A response in flight belongs to the active tab
+                    if self.loading {
+                        ui.disable();
+                    }
+                    let closable = self.request_tabs.len() > 1;
+                    for (i, tab) in self.request_tabs.iter().enumerate() {
+                        let spec = if i == self.active_request_index {
+                            &self.request
+                        } else {
+                            &tab.request
+                        };
+                        let target = reqwest::Url::parse(spec.url.trim())
+                            .map(|url| {
+                                format!("{}{}", url.host_str().unwrap_or_default(), url.path())
+                            })
+                            .unwrap_or_else(|_| spec.url.trim().to_string());
+                        let title = match target.char_indices().nth(32) {
+                            Some((cut, _)) => format!("{}…", &target[..cut]),
+                            None if target.is_empty() => "New request".to_string(),
+                            None => target,
+                        };
+                        if ui
+                            .selectable_label(
+                                i == self.active_request_index,
+                                format!("{:?} {}", spec.method, title),
+                            )
+                            .on_hover_text(spec.url.trim())
+                            .clicked()
+                        {
+                            switch_to = Some(i);
+                        }
+                        if closable && ui.small_button("✖").on_hover_text("Close tab").clicked() {
+                            close = Some(i);
+                        }
+                        ui.separator();
+                    }
+                    if ui
+                        .small_button("➕")
+                        .on_hover_text("New request tab")
+                        .clicked()
+                    {
+                        switch_to = Some(self.request_tabs.len());
+                        self.request_tabs.push(
+                            self.settings
+                                .with_request_defaults(RequestSpec::default())
+                                .into(),
+                        );
+                    }
+                });
+            });
+
+        if let Some(i) = close {
+            self.close_request_tab(i);
+        } else if let Some(i) = switch_to {
+            self.switch_request_tab(i);
+        }
+    }
+
+    fn render_collections_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_collections {
+            return;
+        }
+
+        let mut action = None;
+        egui::SidePanel::left("collections_panel")
+            .resizable(true)
+            .default_width(260.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong("Collections");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("✖").on_hover_text("Close").clicked() {
+                            self.show_collections = false;
+                        }
+                        if ui.button("➕").on_hover_text("New collection").clicked() {
+                            action = Some(CollectionAction::NewCollection);
+                        }
+                    });
+                });
+                ui.menu_button("💾 Save current request", |ui| {
+                    for (ci, collection) in self.collections.iter().enumerate() {
+                        if ui.button(&collection.name).clicked() {
+                            action = Some(CollectionAction::Save(Some(ci)));
+                            ui.close();
+                        }
+                    }
+                    if ui.button("➕ New collection").clicked() {
+                        action = Some(CollectionAction::Save(None));
+                        ui.close();
+                    }
+                });
+                ui.label(
+                    egui::RichText::new(
+                        "Drag requests to reorder or move them; double-click to rename",
+                    )
+                    .weak()
+                    .small(),
+                );
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .id_salt("collections_scroll")
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        if self.collections.is_empty() {
+                            ui.label(
+                                egui::RichText::new("Save a request to start a collection").weak(),
+                            );
+                        }
 
-                let client = reqwest::Client::builder()
-                    .timeout(Duration::from_secs(timeout))
-                    .build()
-                    .unwrap();
-
-                let mut request = match method {
-                    HttpMethod::GET => client.get(&url),
-                    HttpMethod::POST => {
-                        let req = client.post(&url);
-                        match content_type {
-                            ContentType::Json => {
-                                req.body(body).header("Content-Type", "application/json")
+                        for (ci, collection) in self.collections.iter().enumerate() {
+                            if let Some(renaming) = &mut self.renaming
+                                && renaming.target == RenameTarget::Collection(ci)
+                            {
+                                if let Some(done) = rename_field(ui, renaming) {
+                                    action = Some(done);
+                                }
+                                continue;
                             }
-                            ContentType::FormUrlEncoded => {
-                                let mut params = vec![];
-                                for field in &form_data {
-                                    if !field.key.is_empty()
-                                        && field.field_type == FormFieldType::Text
+
+                            let section = egui::CollapsingHeader::new(format!(
+                                "📁 {} ({})",
+                                collection.name,
+                                collection.requests.len()
+                            ))
+                            .id_salt(("collection", ci))
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                for (ri, saved) in collection.requests.iter().enumerate() {
+                                    let here = SavedRef {
+                                        collection: ci,
+                                        index: ri,
+                                    };
+                                    if let Some(renaming) = &mut self.renaming
+                                        && renaming.target == RenameTarget::Request(here)
                                     {
-                                        params.push((field.key.clone(), field.value.clone()));
+                                        if let Some(done) = rename_field(ui, renaming) {
+                                            action = Some(done);
+                                        }
+                                        continue;
                                     }
-                                }
-                                req.form(&params)
-                            }
-                            ContentType::FormData => {
-                                let mut form = reqwest::multipart::Form::new();
-                                for field in form_data {
-                                    if !field.key.is_empty() {
-                                        match field.field_type {
-                                            FormFieldType::Text => {
-                                                form = form.text(field.key, field.value);
-                                            }
-                                            FormFieldType::File => {
-                                                if !field.value.is_empty() {
-                                                    if let Ok(file_content) =
-                                                        std::fs::read(&field.value)
-                                                    {
-                                                        let filename =
-                                                            std::path::Path::new(&field.value)
-                                                                .file_name()
-                                                                .and_then(|n| n.to_str())
-                                                                .unwrap_or("file")
-                                                                .to_string();
-
-                                                        let part = reqwest::multipart::Part::bytes(
-                                                            file_content,
-                                                        )
-                                                        .file_name(filename);
-                                                        form = form.part(field.key, part);
-                                                    }
-                                                }
-                                            }
+
+                                    let row = ui.dnd_drag_source(
+                                        egui::Id::new(("saved_request", ci, ri)),
+                                        here,
+                                        |ui| {
+                                            ui.horizontal(|ui| {
+                                                ui.monospace(format!("{:?}", saved.request.method));
+                                                ui.add(
+                                                    egui::Label::new(&saved.name)
+                                                        .truncate()
+                                                        .sense(egui::Sense::click()),
+                                                )
+                                            })
+                                            .inner
+                                        },
+                                    );
+                                    let label = row.inner.on_hover_text(format!(
+                                        "{}\nClick to load",
+                                        saved.request.url.trim()
+                                    ));
+                                    if label.double_clicked() {
+                                        action = Some(CollectionAction::StartRename(
+                                            RenameTarget::Request(here),
+                                        ));
+                                    } else if label.clicked() {
+                                        action = Some(CollectionAction::Load(here));
+                                    }
+                                    label.context_menu(|ui| {
+                                        if ui.button("Rename").clicked() {
+                                            action = Some(CollectionAction::StartRename(
+                                                RenameTarget::Request(here),
+                                            ));
+                                            ui.close();
                                         }
+                                        if ui.button("Delete").clicked() {
+                                            action = Some(CollectionAction::Delete(here));
+                                            ui.close();
+                                        }
+                                    });
+
+                                    // Dropping onto a request inserts before it
+                                    if row.response.dnd_hover_payload::<SavedRef>().is_some() {
+                                        let rect = row.response.rect;
+                                        ui.painter().hline(
+                                            rect.x_range(),
+                                            rect.top(),
+                                            ui.visuals().selection.stroke,
+                                        );
+                                    }
+                                    if let Some(from) =
+                                        row.response.dnd_release_payload::<SavedRef>()
+                                    {
+                                        action = Some(CollectionAction::Move {
+                                            from: *from,
+                                            collection: ci,
+                                            index: Some(ri),
+                                        });
                                     }
                                 }
-                                req.multipart(form)
+                            });
+
+                            // Dropping onto a collection appends to it
+                            let header = section.header_response;
+                            if let Some(from) = header.dnd_release_payload::<SavedRef>() {
+                                action = Some(CollectionAction::Move {
+                                    from: *from,
+                                    collection: ci,
+                                    index: None,
+                                });
                             }
+                            if header.double_clicked() {
+                                action = Some(CollectionAction::StartRename(
+                                    RenameTarget::Collection(ci),
+                                ));
+                            }
+                            header.context_menu(|ui| {
+                                if ui.button("Rename").clicked() {
+                                    action = Some(CollectionAction::StartRename(
+                                        RenameTarget::Collection(ci),
+                                    ));
+                                    ui.close();
+                                }
+                                if ui.button("Delete collection").clicked() {
+                                    action = Some(CollectionAction::DeleteCollection(ci));
+                                    ui.close();
+                                }
+                            });
+                        }
+                    });
+            });
+
+        if let Some(action) = action {
+            self.apply_collection_action(action);
+        }
+    }
+
+    fn apply_collection_action(&mut self, action: CollectionAction) {
+        match action {
+            CollectionAction::Save(collection) => {
+                let ci = match collection.filter(|&ci| ci < self.collections.len()) {
+                    Some(ci) => ci,
+                    None => self.add_collection(),
+                };
+                let url = self.request.url.trim();
+                let target = reqwest::Url::parse(url)
+                    .map(|u| format!("{}{}", u.host_str().unwrap_or_default(), u.path()))
+                    .unwrap_or_else(|_| url.to_string());
+                self.collections[ci].requests.push(SavedRequest {
+                    name: if target.is_empty() {
+                        "Untitled request".to_string()
+                    } else {
+                        target
+                    },
+                    request: self.request.clone(),
+                });
+            }
+            CollectionAction::NewCollection => {
+                let ci = self.add_collection();
+                self.renaming = Some(Renaming {
+                    target: RenameTarget::Collection(ci),
+                    text: self.collections[ci].name.clone(),
+                    focus: true,
+                });
+            }
+            CollectionAction::Load(at) => {
+                if let Some(saved) = self.saved_request(at) {
+                    self.load_request(saved.request.clone());
+                }
+            }
+            CollectionAction::Move {
+                from,
+                collection,
+                index,
+            } => self.move_saved_request(from, collection, index),
+            CollectionAction::StartRename(target) => {
+                let text = match target {
+                    RenameTarget::Collection(ci) => {
+                        self.collections.get(ci).map(|c| c.name.clone())
+                    }
+                    RenameTarget::Request(at) => self.saved_request(at).map(|s| s.name.clone()),
+                };
+                self.renaming = text.map(|text| Renaming {
+                    target,
+                    text,
+                    focus: true,
+                });
+            }
+            CollectionAction::FinishRename => {
+                let Some(renaming) = self.renaming.take() else {
+                    return;
+                };
+                let name = renaming.text.trim().to_string();
+                if name.is_empty() {
+                    return;
+                }
+                match renaming.target {
+                    RenameTarget::Collection(ci) => {
+                        if let Some(collection) = self.collections.get_mut(ci) {
+                            collection.name = name;
+                        }
+                    }
+                    RenameTarget::Request(at) => {
+                        if let Some(saved) = self
+                            .collections
+                            .get_mut(at.collection)
+                            .and_then(|c| c.requests.get_mut(at.index))
+                        {
+                            saved.name = name;
                         }
                     }
-                    HttpMethod::PUT => {
-                        let req = client.put(&url);
-                        match content_type {
-                            ContentType::Json => {
-                                req.body(body).header("Content-Type", "application/json")
+                }
+            }
+            CollectionAction::CancelRename => self.renaming = None,
+            CollectionAction::Delete(at) => {
+                if self.saved_request(at).is_some() {
+                    self.collections[at.collection].requests.remove(at.index);
+                }
+                self.renaming = None;
+            }
+            CollectionAction::DeleteCollection(ci) => {
+                if ci < self.collections.len() {
+                    self.collections.remove(ci);
+                }
+                self.renaming = None;
+            }
+        }
+    }
+
+    fn add_collection(&mut self) -> usize {
+        self.collections.push(Collection {
+            name: format!("Collection {}", self.collections.len() + 1),
+            requests: Vec::new(),
+        });
+        self.collections.len() - 1
+    }
+
+    fn saved_request(&self, at: SavedRef) -> Option<&SavedRequest> {
+        self.collections
+            .get(at.collection)
+            .and_then(|c| c.requests.get(at.index))
+    }
+
+    /// Moves a saved request before `index` in `collection`, or to its end.
+    /// Every position is checked before anything is removed, so a drop onto
+    /// a stale target leaves the collections untouched.
+    fn move_saved_request(&mut self, from: SavedRef, collection: usize, index: Option<usize>) {
+        if self.saved_request(from).is_none() || collection >= self.collections.len() {
+            return;
+        }
+        let mut index = index.unwrap_or(self.collections[collection].requests.len());
+        if collection == from.collection && index > from.index {
+            index -= 1;
+        }
+        let saved = self.collections[from.collection]
+            .requests
+            .remove(from.index);
+        let requests = &mut self.collections[collection].requests;
+        requests.insert(index.min(requests.len()), saved);
+    }
+
+    fn render_history_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_history {
+            return;
+        }
+
+        let mut load = None;
+        egui::SidePanel::left("history_panel")
+            .resizable(true)
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong("History");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("✖").on_hover_text("Close").clicked() {
+                            self.show_history = false;
+                        }
+                        if ui.button("🗑").on_hover_text("Clear history").clicked() {
+                            self.history.clear();
+                        }
+                    });
+                });
+                let failures = self.history.iter().filter(|e| e.failed()).count();
+                ui.toggle_value(
+                    &mut self.history_errors_only,
+                    format!("⚠ Errors only ({})", failures),
+                )
+                .on_hover_text("Show only transport errors and 4xx/5xx responses");
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .id_salt("history_scroll")
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        let entries = self
+                            .history
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, e)| !self.history_errors_only || e.failed());
+                        for (i, entry) in entries {
+                            let color = match status_code(&entry.status) {
+                                Some(code) => status_class(code).1,
+                                None if entry.status == "Error" => {
+                                    egui::Color32::from_rgb(200, 100, 100)
+                                }
+                                None => ui.visuals().weak_text_color(),
+                            };
+                            let row = ui
+                                .horizontal(|ui| {
+                                    ui.colored_label(color, entry.status.trim());
+                                    ui.monospace(format!("{:?}", entry.request.method));
+                                    ui.add(
+                                        egui::Label::new(entry.request.url.trim())
+                                            .truncate()
+                                            .sense(egui::Sense::click()),
+                                    )
+                                })
+                                .inner;
+                            let mut hover = entry.time.clone();
+                            if let Some(elapsed) = entry.elapsed {
+                                hover.push_str(&format!(" · {} ms", elapsed.as_millis()));
+                            }
+                            if row
+                                .on_hover_text(format!("{}\nClick to load", hover))
+                                .clicked()
+                            {
+                                load = Some(i);
+                            }
+                        }
+                    });
+            });
+
+        if let Some(i) = load {
+            self.load_request(self.history[i].request.clone());
+        }
+    }
+
+    fn render_security_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_security_audit {
+            return;
+        }
+
+        egui::SidePanel::right("security_panel")
+            .resizable(true)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong("Security headers");
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("✖").on_hover_text("Close").clicked() {
+                            self.show_security_audit = false;
+                        }
+                    });
+                });
+                ui.separator();
+
+                if self.security_audit.is_empty() {
+                    ui.label(egui::RichText::new("Send a request to audit its response").weak());
+                    return;
+                }
+                let applicable = self
+                    .security_audit
+                    .iter()
+                    .filter(|check| check.verdict != Verdict::NotApplicable);
+                let present = applicable
+                    .clone()
+                    .filter(|check| check.value.is_some())
+                    .count();
+                ui.label(format!("{} of {} present", present, applicable.count()));
+                ui.add_space(4.0);
+
+                egui::ScrollArea::vertical()
+                    .id_salt("security_scroll")
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for check in &self.security_audit {
+                            let (icon, color) = match check.verdict {
+                                Verdict::Good => ("✔", egui::Color32::from_rgb(100, 200, 100)),
+                                Verdict::Weak => ("⚠", egui::Color32::from_rgb(255, 165, 0)),
+                                Verdict::Missing => ("✖", egui::Color32::from_rgb(200, 100, 100)),
+                                Verdict::NotApplicable => ("–", ui.visuals().weak_text_color()),
+                            };
+                            ui.horizontal(|ui| {
+                                ui.colored_label(color, icon);
+                                ui.strong(check.header);
+                            });
+                            if let Some(value) = &check.value {
+                                ui.label(egui::RichText::new(value).monospace().weak());
+                            }
+                            ui.label(&check.note);
+                            ui.separator();
+                        }
+                    });
+            });
+    }
+
+    fn render_log_panel(&mut self, ctx: &egui::Context) {
+        if !self.show_log {
+            return;
+        }
+
+        egui::TopBottomPanel::bottom("log_panel")
+            .resizable(true)
+            .default_height(160.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong("Log");
+                    ui.label(egui::RichText::new(format!("{} entries", self.log.len())).weak());
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("✖").on_hover_text("Close").clicked() {
+                            self.show_log = false;
+                        }
+                        if ui.button("🗑 Clear").clicked() {
+                            self.log.clear();
+                        }
+                        if ui.button("📋 Copy all").clicked() {
+                            let text = self
+                                .log
+                                .iter()
+                                .map(|e| format!("{} {}", e.time, e.message))
+                                .collect::<Vec<_>>()
+                                .join("\n");
+                            ui.ctx().copy_text(text);
+                        }
+                    });
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .id_salt("log_scroll")
+                    .auto_shrink([false, false])
+                    .stick_to_bottom(true)
+                    .show(ui, |ui| {
+                        for entry in &self.log {
+                            let color = match entry.level {
+                                LogLevel::Info => ui.visuals().text_color(),
+                                LogLevel::Warn => egui::Color32::from_rgb(255, 165, 0),
+                                LogLevel::Error => egui::Color32::from_rgb(200, 100, 100),
+                            };
+                            ui.label(
+                                egui::RichText::new(format!("{} {}", entry.time, entry.message))
+                                    .monospace()
+                                    .color(color),
+                            );
+                        }
+                    });
+            });
+    }
+
+    fn import_har(&mut self) {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("HAR", &["har", "json"])
+            .pick_file()
+        else {
+            return;
+        };
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| har::import(&text))
+        {
+            Ok(entries) => {
+                self.log(
+                    LogLevel::Info,
+                    format!("Imported {} HAR entries from {}", entries.len(), name),
+                );
+                self.har_error = None;
+                self.har_import = Some((name, entries));
+            }
+            Err(e) => {
+                self.log(LogLevel::Error, format!("HAR import failed: {}", e));
+                self.har_error = Some(e);
+            }
+        }
+    }
+
+    fn export_har(&mut self) {
+        let Some(request) = &self.last_request else {
+            return;
+        };
+        let response = HttpResponse {
+            status: self.response_status.clone(),
+            header_map: self.response_header_map.clone(),
+            body: self.response_body.clone(),
+            raw_body: self.response_raw_body.clone(),
+            is_binary: self.is_response_binary,
+            bytes: self.response_bytes.clone(),
+            content_type: self.response_content_type.clone(),
+            location: self.response_location.clone(),
+            elapsed: self
+                .response_metrics
+                .as_ref()
+                .map(|m| m.elapsed)
+                .unwrap_or_default(),
+            request_size: self.response_metrics.as_ref().and_then(|m| m.request_size),
+            response_size: self
+                .response_metrics
+                .as_ref()
+                .map(|m| m.response_size)
+                .unwrap_or_default(),
+            http_version: self
+                .response_metrics
+                .as_ref()
+                .map(|m| m.http_version.clone())
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+        let document = har::export(
+            request,
+            &response,
+            self.last_sent_at.unwrap_or_else(std::time::SystemTime::now),
+        );
+
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("HAR", &["har"])
+            .set_file_name("crabipie.har")
+            .save_file()
+        {
+            let result = std::fs::write(
+                &path,
+                serde_json::to_string_pretty(&document).unwrap_or_default(),
+            );
+            match result {
+                Ok(()) => self.log(
+                    LogLevel::Info,
+                    format!("Exported HAR to {}", path.display()),
+                ),
+                Err(e) => self.log(LogLevel::Error, format!("HAR export failed: {}", e)),
+            }
+        }
+    }
+
+    fn load_har_entry(&mut self, spec: RequestSpec) {
+        let mut spec = RequestSpec {
+            timeout: self.request.timeout,
+            cookies: vec![CookieRow::default()],
+            ..spec
+        };
+        if spec.form_data.is_empty() {
+            spec.form_data.push(FormField::default());
+        }
+        self.load_request(spec);
+    }
+
+    fn render_har_import(&mut self, ctx: &egui::Context) {
+        let Some((name, entries)) = &self.har_import else {
+            return;
+        };
+
+        let mut open = true;
+        let mut load = None;
+        egui::Window::new(format!("HAR: {}", name))
+            .open(&mut open)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("har_entries")
+                            .num_columns(4)
+                            .striped(true)
+                            .spacing([10.0, 4.0])
+                            .show(ui, |ui| {
+                                for (i, entry) in entries.iter().enumerate() {
+                                    ui.add_enabled_ui(entry.spec.is_some(), |ui| {
+                                        if ui.button("Load").clicked() {
+                                            load = Some(i);
+                                        }
+                                    });
+                                    ui.monospace(&entry.method);
+                                    ui.label(
+                                        entry
+                                            .status
+                                            .map(|s| s.to_string())
+                                            .unwrap_or_else(|| "—".to_string()),
+                                    );
+                                    let url = ui.add(egui::Label::new(&entry.url).truncate());
+                                    if !entry.warnings.is_empty() {
+                                        url.on_hover_text(entry.warnings.join("\n"));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        if let Some(i) = load
+            && let Some(spec) = entries[i].spec.clone()
+        {
+            let warnings = entries[i].warnings.clone();
+            self.log(
+                LogLevel::Info,
+                format!("Loaded {:?} {} from HAR", spec.method, spec.url),
+            );
+            for warning in warnings {
+                self.log(LogLevel::Warn, format!("HAR: {}", warning));
+            }
+            self.load_har_entry(spec);
+        }
+        if !open {
+            self.har_import = None;
+        }
+    }
+
+    fn start_connection_check(&mut self) {
+        // The check connects to the host, which a dry run promises not to
+        if self.dry_run {
+            return;
+        }
+        self.checking_connection = true;
+        self.connection_check = None;
+
+        let client = self.client.clone();
+        // The URL Send would use, variables and params included
+        let url = self.prepared_request().full_url();
+        let timeout = self.request.timeout.min(Duration::from_secs(10));
+        let tx = self.check_tx.clone();
+        self.runtime.spawn(async move {
+            let _ = tx.send(client::check_connection(&client, &url, timeout).await);
+        });
+    }
+
+    fn render_connection_check(&mut self, ui: &mut egui::Ui) {
+        if let Ok(result) = self.check_rx.try_recv() {
+            self.checking_connection = false;
+            let (level, message) = match &result {
+                Err(e) => (LogLevel::Error, format!("Connection check failed: {}", e)),
+                Ok(check) => match (&check.tcp, &check.head) {
+                    (Err(e), _) => (
+                        LogLevel::Error,
+                        format!("Connection check {}: unreachable: {}", check.address, e),
+                    ),
+                    (Ok(latency), Some(Err(e))) => (
+                        LogLevel::Warn,
+                        format!(
+                            "Connection check {}: TCP {} ms, HEAD failed: {}",
+                            check.address,
+                            latency.as_millis(),
+                            e
+                        ),
+                    ),
+                    (Ok(latency), head) => (
+                        LogLevel::Info,
+                        format!(
+                            "Connection check {}: TCP {} ms{}",
+                            check.address,
+                            latency.as_millis(),
+                            head.as_ref()
+                                .and_then(|h| h.as_ref().ok())
+                                .map(|(status, t)| format!(
+                                    ", HEAD {} in {} ms",
+                                    status,
+                                    t.as_millis()
+                                ))
+                                .unwrap_or_default()
+                        ),
+                    ),
+                },
+            };
+            self.log(level, message);
+            self.connection_check = Some(result);
+        }
+        if !self.checking_connection && self.connection_check.is_none() {
+            return;
+        }
+
+        let ok = egui::Color32::from_rgb(100, 200, 100);
+        let warn = egui::Color32::from_rgb(255, 165, 0);
+        let error = egui::Color32::from_rgb(200, 100, 100);
+
+        ui.horizontal(|ui| {
+            if self.checking_connection {
+                ui.spinner();
+                ui.label("Checking connection...");
+                return;
+            }
+            let Some(result) = &self.connection_check else {
+                return;
+            };
+
+            match result {
+                Err(e) => {
+                    ui.colored_label(error, format!("📡 {}", e));
+                }
+                Ok(check) => {
+                    ui.label(format!("📡 {}", check.address));
+                    match &check.tcp {
+                        Ok(latency) => {
+                            ui.colored_label(ok, format!("TCP {} ms", latency.as_millis()));
+                        }
+                        Err(e) => {
+                            ui.colored_label(error, format!("Unreachable: {}", e));
+                        }
+                    }
+                    match &check.head {
+                        Some(Ok((status, elapsed))) => {
+                            ui.separator();
+                            if check.tls {
+                                ui.colored_label(ok, "TLS ✔");
+                            }
+                            ui.label(format!("HEAD {} in {} ms", status, elapsed.as_millis()));
+                        }
+                        Some(Err(e)) => {
+                            ui.separator();
+                            let what = if check.tls {
+                                "TLS/HEAD failed"
+                            } else {
+                                "HEAD failed"
+                            };
+                            ui.colored_label(warn, what).on_hover_text(e);
+                        }
+                        None => {}
+                    }
+                }
+            }
+
+            if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                self.connection_check = None;
+            }
+        });
+    }
+
+    fn poll_repeat_run(&mut self) {
+        let mut received = false;
+        while let Ok(resp) = self.repeat_rx.try_recv() {
+            received = true;
+            self.repeat.completed += 1;
+            *self.repeat.statuses.entry(resp.status).or_default() += 1;
+            if !resp.http_version.is_empty() {
+                self.repeat.timings.push(resp.elapsed);
+            }
+        }
+
+        if received && !self.repeat.running() {
+            let tally = self
+                .repeat
+                .statuses
+                .iter()
+                .map(|(status, count)| format!("{} × {}", count, status))
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.log(LogLevel::Info, format!("Repeat run finished: {}", tally));
+        }
+    }
+
+    fn render_repeat(&mut self, ctx: &egui::Context) {
+        if !self.show_repeat {
+            return;
+        }
+
+        let mut open = self.show_repeat;
+        egui::Window::new("Repeat request")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let running = self.repeat.running();
+                ui.horizontal(|ui| {
+                    ui.label("Send");
+                    ui.add_enabled(
+                        !running,
+                        egui::DragValue::new(&mut self.repeat.count).range(1..=10_000),
+                    );
+                    ui.label("times");
+                });
+                ui.label(
+                    egui::RichText::new(format!(
+                        "Up to {} at a time (change in Settings)",
+                        self.settings.max_concurrent_requests.max(1)
+                    ))
+                    .weak(),
+                );
+
+                ui.horizontal(|ui| {
+                    if running {
+                        if ui.button("⏹ Stop").clicked() {
+                            self.repeat.cancel_flag.store(true, Ordering::Relaxed);
+                        }
+                    } else if ui
+                        .add_enabled(
+                            !self.dry_run && !self.request.url.trim().is_empty(),
+                            egui::Button::new("▶ Run"),
+                        )
+                        .on_disabled_hover_text("Turn off dry run to send requests")
+                        .clicked()
+                    {
+                        self.start_repeat_run();
+                    }
+                });
+
+                if self.repeat.total == 0 {
+                    return;
+                }
+
+                ui.add_space(6.0);
+                ui.add(
+                    egui::ProgressBar::new(self.repeat.completed as f32 / self.repeat.total as f32)
+                        .text(format!(
+                            "{} of {} completed",
+                            self.repeat.completed, self.repeat.total
+                        )),
+                );
+
+                ui.add_space(6.0);
+                egui::Grid::new("repeat_statuses")
+                    .num_columns(2)
+                    .spacing([12.0, 4.0])
+                    .show(ui, |ui| {
+                        for (status, count) in &self.repeat.statuses {
+                            ui.label(status);
+                            ui.label(count.to_string());
+                            ui.end_row();
+                        }
+                    });
+
+                let timings = &self.repeat.timings;
+                if let (Some(min), Some(max)) = (timings.iter().min(), timings.iter().max()) {
+                    let avg = timings.iter().sum::<Duration>() / timings.len() as u32;
+                    ui.add_space(4.0);
+                    ui.label(format!(
+                        "min {} ms · avg {} ms · max {} ms",
+                        min.as_millis(),
+                        avg.as_millis(),
+                        max.as_millis()
+                    ));
+                }
+            });
+        self.show_repeat = open;
+    }
+
+    fn current_baseline(&self) -> Option<Baseline> {
+        let request = self.last_request.as_ref()?;
+        Some(Baseline {
+            method: request.method.clone(),
+            url: request.url.trim().to_string(),
+            status: self.response_status.clone(),
+            body: self.response_raw_body.clone(),
+            bytes: self.response_bytes.clone(),
+        })
+    }
+
+    /// Compares the current response with the pinned baseline, if both are
+    /// for the same method and URL. The error describes the first difference.
+    fn baseline_comparison(&self) -> Option<Result<(), String>> {
+        let baseline = self.baseline.as_ref()?;
+        let request = self.last_request.as_ref()?;
+        if self.loading || request.method != baseline.method || request.url.trim() != baseline.url {
+            return None;
+        }
+
+        if self.response_status != baseline.status {
+            return Some(Err(format!(
+                "Status: baseline {}, now {}",
+                baseline.status, self.response_status
+            )));
+        }
+        if self.response_bytes != baseline.bytes {
+            return Some(Err(format!(
+                "Binary body differs: baseline {}, now {}",
+                format_size(baseline.bytes.len()),
+                format_size(self.response_bytes.len())
+            )));
+        }
+        if self.response_raw_body != baseline.body {
+            let mut now = self.response_raw_body.lines();
+            let mut then = baseline.body.lines();
+            let mut line = 1;
+            loop {
+                match (then.next(), now.next()) {
+                    (Some(a), Some(b)) if a == b => line += 1,
+                    (a, b) => {
+                        return Some(Err(format!(
+                            "Body differs from line {}:\n- {}\n+ {}",
+                            line,
+                            a.unwrap_or("(end of body)"),
+                            b.unwrap_or("(end of body)")
+                        )));
+                    }
+                }
+            }
+        }
+        Some(Ok(()))
+    }
+
+    fn open_response_in_browser(&self) {
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let path = std::env::temp_dir().join(format!("crabipie_response_{}.html", stamp));
+        if std::fs::write(&path, &self.response_body).is_ok() {
+            let _ = opener::open_browser(&path);
+        }
+    }
+
+    /// Writes the current response and the request that produced it into a new
+    /// `crabipie_bundle_<millis>` directory under `parent`. The Authorization
+    /// value is redacted so bundles can be attached to bug reports.
+    fn save_response_bundle(
+        &self,
+        parent: &std::path::Path,
+    ) -> std::io::Result<std::path::PathBuf> {
+        let stamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or_default();
+        let dir = parent.join(format!("crabipie_bundle_{}", stamp));
+        std::fs::create_dir_all(&dir)?;
+
+        let extension = extension_for_content_type(
+            &self.response_content_type,
+            &self.response_filename,
+            self.is_response_binary,
+        );
+        let body_name = format!("body.{}", extension);
+        if self.is_response_binary {
+            std::fs::write(dir.join(&body_name), &self.response_bytes)?;
+        } else {
+            std::fs::write(dir.join(&body_name), &self.response_body)?;
+        }
+        std::fs::write(dir.join("headers.txt"), &self.response_headers)?;
+        if !self.response_trailers.is_empty() {
+            std::fs::write(dir.join("trailers.txt"), &self.response_trailers)?;
+        }
+
+        let request = self.last_request.as_ref().map(|spec| {
+            let headers: serde_json::Map<String, serde_json::Value> = spec
+                .header_map()
+                .iter()
+                .map(|(name, value)| {
+                    let value = if name == reqwest::header::AUTHORIZATION {
+                        "<redacted>".to_string()
+                    } else {
+                        value.to_str().unwrap_or("<binary>").to_string()
+                    };
+                    (name.to_string(), serde_json::Value::String(value))
+                })
+                .collect();
+            let body = match spec.content_type {
+                _ if !spec.method.has_body() => serde_json::Value::Null,
+                ContentType::Json | ContentType::Auto => {
+                    serde_json::Value::String(spec.body.clone())
+                }
+                ContentType::FormData | ContentType::FormUrlEncoded => spec
+                    .form_data
+                    .iter()
+                    .filter(|f| !f.key.is_empty())
+                    .map(|f| {
+                        if f.field_type == FormFieldType::File {
+                            serde_json::json!({ "key": f.key, "files": f.files })
+                        } else {
+                            serde_json::json!({ "key": f.key, "value": f.value })
+                        }
+                    })
+                    .collect(),
+            };
+            serde_json::json!({
+                "method": format!("{:?}", spec.method),
+                "url": spec.url,
+                "headers": headers,
+                "body": body,
+                "timeout_secs": spec.timeout.as_secs(),
+            })
+        });
+
+        let metadata = serde_json::json!({
+            "status": self.response_status,
+            "content_type": self.response_content_type,
+            "body_file": body_name,
+            "location": self.response_location,
+            "elapsed_ms": self.response_metrics.as_ref().map(|m| m.elapsed.as_millis() as u64),
+            "request_size": self.response_metrics.as_ref().and_then(|m| m.request_size),
+            "response_size": self.response_metrics.as_ref().map(|m| m.response_size),
+            "http_version": self.response_metrics.as_ref().map(|m| m.http_version.clone()),
+            "request": request,
+        });
+        std::fs::write(
+            dir.join("response.json"),
+            serde_json::to_string_pretty(&metadata).unwrap_or_default(),
+        )?;
+
+        Ok(dir)
+    }
+
+    fn load_body_from_file(&mut self) {
+        if let Some(path) = &self.body_file
+            && let Ok(contents) = std::fs::read_to_string(path)
+        {
+            self.request.body = contents;
+        }
+    }
+
+    fn set_body_watch(&mut self, ctx: &egui::Context, enabled: bool) {
+        self.body_watcher = None;
+        self.body_watch_rx = None;
+        self.auto_send_pending = false;
+
+        let Some(path) = self.body_file.clone() else {
+            return;
+        };
+        if !enabled {
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        let watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+            ctx.request_repaint();
+        });
+
+        // Watch the directory rather than the file, since many editors save by
+        // writing a temp file and renaming it over the original
+        let dir = path
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+            .unwrap_or(std::path::Path::new("."));
+        if let Ok(mut watcher) = watcher
+            && watcher.watch(dir, RecursiveMode::NonRecursive).is_ok()
+        {
+            self.body_watcher = Some(watcher);
+            self.body_watch_rx = Some(rx);
+        }
+    }
+
+    fn poll_body_watch(&mut self) {
+        let (Some(rx), Some(path)) = (&self.body_watch_rx, &self.body_file) else {
+            return;
+        };
+
+        let mut changed = false;
+        while let Ok(event) = rx.try_recv() {
+            if let Ok(event) = event
+                && (event.kind.is_modify() || event.kind.is_create())
+                && event
+                    .paths
+                    .iter()
+                    .any(|p| p.file_name() == path.file_name())
+            {
+                changed = true;
+            }
+        }
+
+        if changed {
+            self.load_body_from_file();
+            self.auto_send_pending = true;
+        }
+        // A change during a request is sent as soon as that request finishes
+        if self.auto_send_pending && !self.loading {
+            self.auto_send_pending = false;
+            if !self.request.url.trim().is_empty() {
+                self.send_request();
+                self.last_auto_send = Some(std::time::Instant::now());
+            }
+        }
+    }
+
+    fn expected_status_code(&self) -> Option<u16> {
+        self.request.expected_status.trim().parse().ok()
+    }
+
+    fn response_status_code(&self) -> Option<u16> {
+        status_code(&self.response_status)
+    }
+
+    fn set_polling(&mut self, on: bool) {
+        if on == self.polling.running() {
+            return;
+        }
+        if on {
+            self.polling.next_send = Some(std::time::Instant::now());
+            self.polling.sent = 0;
+            self.log(
+                LogLevel::Info,
+                format!("Polling every {}s", self.polling.interval_secs),
+            );
+        } else {
+            self.polling.next_send = None;
+            self.log(
+                LogLevel::Info,
+                format!("Polling stopped after {} requests", self.polling.sent),
+            );
+        }
+        self.polling.paused_remaining = None;
+    }
+
+    /// Sends the request when the polling interval is up. The countdown holds
+    /// while a text field has keyboard focus, so edits aren't sent half done.
+    fn tick_polling(&mut self, ctx: &egui::Context) {
+        let Some(next) = self.polling.next_send else {
+            return;
+        };
+        let now = std::time::Instant::now();
+        if ctx.wants_keyboard_input() {
+            let remaining = *self
+                .polling
+                .paused_remaining
+                .get_or_insert_with(|| next.saturating_duration_since(now));
+            self.polling.next_send = Some(now + remaining);
+            return;
+        }
+        self.polling.paused_remaining = None;
+
+        if now >= next && !self.loading && !self.request.url.trim().is_empty() {
+            self.send_request();
+            self.polling.sent += 1;
+            self.polling.next_send =
+                Some(now + Duration::from_secs(self.polling.interval_secs.max(1)));
+        }
+    }
+
+    fn cancel_request(&mut self) {
+        self.set_polling(false);
+        self.log(LogLevel::Warn, "Request cancelled by user");
+        self.cancel_flag.store(true, Ordering::Relaxed);
+        self.loading = false;
+        self.request_start_time = None;
+        self.response_body = "Request cancelled by user".to_string();
+        self.response_status = "Cancelled".to_string();
+    }
+
+    fn get_elapsed_time(&self) -> Option<Duration> {
+        self.request_start_time.map(|start| start.elapsed())
+    }
+
+    fn should_show_cancel_button(&self) -> bool {
+        if !self.loading {
+            return false;
+        }
+
+        if let Some(elapsed) = self.get_elapsed_time() {
+            // Show cancel button if elapsed time >= timeout
+            elapsed >= self.request.timeout
+        } else {
+            false
+        }
+    }
+
+    fn render_metrics_bar(&self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let Some(metrics) = &self.response_metrics else {
+                ui.label(egui::RichText::new("No response yet").weak());
+                return;
+            };
+
+            ui.label(format!("⏱ {} ms", metrics.elapsed.as_millis()))
+                .on_hover_text("Time until the full body was received");
+            if let Some(format_time) = metrics.format_time {
+                let slow = format_time >= Duration::from_millis(100);
+                let text = format!("format {} ms", format_time.as_millis());
+                if slow {
+                    ui.colored_label(egui::Color32::from_rgb(255, 165, 0), text)
+                } else {
+                    ui.label(text)
+                }
+                .on_hover_text("Decoding and pretty printing the body, after it was received");
+            }
+            ui.separator();
+            ui.label(format!(
+                "⬆ {}",
+                metrics
+                    .request_size
+                    .map(format_size)
+                    .unwrap_or_else(|| "streamed".to_string())
+            ))
+            .on_hover_text("Request body size");
+            ui.separator();
+            ui.label(format!("⬇ {}", format_size(metrics.response_size)))
+                .on_hover_text("Response body size");
+            if !metrics.encoding_note.is_empty() {
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 165, 0),
+                    format!("⚠ ({})", metrics.content_encoding),
+                )
+                .on_hover_text(&metrics.encoding_note);
+            } else if !metrics.content_encoding.is_empty() {
+                ui.label(format!("({})", metrics.content_encoding))
+                    .on_hover_text("Content-Encoding, decoded for display");
+            }
+            if let Some(limit) = metrics.throttle {
+                let transferred = metrics.request_size.unwrap_or(0) + metrics.response_size;
+                let effective = transferred as f64 / metrics.elapsed.as_secs_f64().max(0.001);
+                ui.separator();
+                ui.colored_label(
+                    egui::Color32::from_rgb(255, 165, 0),
+                    format!("🐢 {}/s", format_size(effective as usize)),
+                )
+                .on_hover_text(format!(
+                    "Effective rate while throttled to {}/s",
+                    format_size(limit as usize)
+                ));
+            }
+
+            if let Some(code) = self.response_status_code() {
+                let (class, color) = status_class(code);
+                ui.separator();
+                ui.colored_label(color, class);
+            }
+
+            ui.separator();
+            ui.label(&metrics.http_version);
+        });
+    }
+
+    fn render_shortcut_settings(&mut self, ui: &mut egui::Ui) {
+        // Record the next key combination pressed, Escape cancels. Keys without
+        // Ctrl or Alt are ignored unless they are function keys, since they
+        // would fire while typing.
+        if let Some(action) = self.recording_shortcut {
+            let recorded = ui.input(|i| {
+                i.events.iter().find_map(|event| match event {
+                    egui::Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some(egui::KeyboardShortcut::new(*modifiers, *key)),
+                    _ => None,
+                })
+            });
+            if let Some(shortcut) = recorded {
+                let modifiers = shortcut.modifiers;
+                let function_key = shortcut
+                    .logical_key
+                    .name()
+                    .strip_prefix('F')
+                    .is_some_and(|n| n.parse::<u8>().is_ok());
+                let cancel = shortcut.logical_key == egui::Key::Escape && modifiers.is_none();
+                if cancel {
+                    self.recording_shortcut = None;
+                } else if modifiers.ctrl || modifiers.command || modifiers.alt || function_key {
+                    // Store plain Ctrl so the binding means the same on every platform
+                    let mut modifiers = modifiers;
+                    modifiers.ctrl |= modifiers.command;
+                    modifiers.command = false;
+                    modifiers.mac_cmd = false;
+                    let shortcut = egui::KeyboardShortcut::new(modifiers, shortcut.logical_key);
+                    if shortcut == action.default_shortcut() {
+                        self.settings.shortcuts.remove(&action);
+                    } else {
+                        self.settings.shortcuts.insert(action, shortcut);
+                    }
+                    self.recording_shortcut = None;
+                }
+            }
+        }
+
+        egui::Grid::new("shortcut_settings")
+            .num_columns(3)
+            .spacing([12.0, 4.0])
+            .show(ui, |ui| {
+                for action in ShortcutAction::ALL {
+                    ui.label(action.label());
+                    let recording = self.recording_shortcut == Some(action);
+                    let text = if recording {
+                        "Press Ctrl/Alt + key, or an F key…".to_string()
+                    } else {
+                        format_shortcut(&self.settings.shortcut(action))
+                    };
+                    ui.horizontal(|ui| {
+                        if ui
+                            .selectable_label(recording, text)
+                            .on_hover_text("Click, then press the new key combination")
+                            .clicked()
+                        {
+                            self.recording_shortcut = (!recording).then_some(action);
+                        }
+                        if self.settings.shortcuts.contains_key(&action)
+                            && ui
+                                .small_button("↺")
+                                .on_hover_text("Reset to default")
+                                .clicked()
+                        {
+                            self.settings.shortcuts.remove(&action);
+                        }
+                    });
+                    let conflicts = self.settings.shortcut_conflicts(action);
+                    if conflicts.is_empty() {
+                        ui.label("");
+                    } else {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(255, 165, 0),
+                            format!("⚠ Also {}", conflicts.join(", ")),
+                        );
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    fn render_settings(&mut self, ctx: &egui::Context) {
+        if !self.show_settings {
+            return;
+        }
+
+        let mut open = self.show_settings;
+        let mut pool_changed = false;
+        egui::Window::new("Settings")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.strong("Display");
+                ui.add_space(4.0);
+                ui.checkbox(&mut self.settings.show_metrics_bar, "Show metrics bar");
+                ui.checkbox(
+                    &mut self.settings.format_on_paste,
+                    "Prettify JSON pasted into the body",
+                );
+                ui.checkbox(
+                    &mut self.settings.keep_response_tab,
+                    "Keep current response tab on new response",
+                )
+                .on_hover_text("Otherwise each response switches to the Body tab");
+                ui.checkbox(
+                    &mut self.settings.sync_json_views,
+                    "Sync JSON tree selection with the Body cursor",
+                )
+                .on_hover_text("Double-click a tree node to jump to it in the Body");
+                ui.horizontal(|ui| {
+                    ui.label("Pretty print responses:");
+                    for format in ["json", "xml"] {
+                        let mut pretty = self.view_pretty(format);
+                        if ui.checkbox(&mut pretty, format.to_uppercase()).changed() {
+                            self.settings
+                                .pretty_by_format
+                                .insert(format.to_string(), pretty);
+                            self.apply_body_view();
+                        }
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let mut collapse = self.settings.collapse_body_over_kb.is_some();
+                    if ui
+                        .checkbox(&mut collapse, "Collapse response bodies over")
+                        .changed()
+                    {
+                        self.settings.collapse_body_over_kb = collapse.then_some(1024);
+                    }
+                    if let Some(kb) = &mut self.settings.collapse_body_over_kb {
+                        ui.add(
+                            egui::DragValue::new(kb)
+                                .range(64..=1024 * 1024)
+                                .suffix(" KB"),
+                        );
+                    }
+                })
+                .response
+                .on_hover_text(format!(
+                    "Larger bodies show their first {} until you ask for the rest",
+                    format_size(COLLAPSED_BODY_PREVIEW)
+                ));
+                ui.checkbox(
+                    &mut self.settings.background_formatting,
+                    "Prettify large JSON in the background",
+                )
+                .on_hover_text(format!(
+                    "Bodies over {} are formatted on a worker thread",
+                    format_size(BACKGROUND_FORMAT_THRESHOLD)
+                ));
+
+                ui.add_space(8.0);
+                ui.strong("Requests");
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    ui.label("New requests:");
+                    egui::ComboBox::from_id_salt("default_method")
+                        .selected_text(format!("{:?}", self.settings.default_method))
+                        .show_ui(ui, |ui| {
+                            for method in HttpMethod::ALL {
+                                let label = format!("{:?}", method);
+                                ui.selectable_value(
+                                    &mut self.settings.default_method,
+                                    method,
+                                    label,
+                                );
+                            }
+                        });
+                    egui::ComboBox::from_id_salt("default_content_type")
+                        .selected_text(content_type_label(&self.settings.default_content_type))
+                        .show_ui(ui, |ui| {
+                            for content_type in CONTENT_TYPES {
+                                let label = content_type_label(&content_type);
+                                ui.selectable_value(
+                                    &mut self.settings.default_content_type,
+                                    content_type,
+                                    label,
+                                );
+                            }
+                        });
+                });
+                ui.checkbox(
+                    &mut self.settings.split_pasted_query,
+                    "Move the query of pasted URLs into Params",
+                );
+                ui.checkbox(
+                    &mut self.settings.strict_header_trim,
+                    "Strictly trim header names and values",
+                )
+                .on_hover_text(
+                    "Also strip zero-width characters and byte order marks that come along \
+                     with pasted headers and tokens",
+                );
+                ui.horizontal(|ui| {
+                    ui.label("Accept-Encoding:");
+                    egui::ComboBox::from_id_salt("accept_encoding")
+                        .selected_text(if self.settings.accept_encoding.is_empty() {
+                            "Not sent"
+                        } else {
+                            self.settings.accept_encoding.as_str()
+                        })
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(
+                                &mut self.settings.accept_encoding,
+                                String::new(),
+                                "Not sent",
+                            );
+                            for encoding in
+                                ["gzip, deflate, br", "gzip", "br", "deflate", "identity"]
+                            {
+                                ui.selectable_value(
+                                    &mut self.settings.accept_encoding,
+                                    encoding.to_string(),
+                                    encoding,
+                                );
+                            }
+                        });
+                })
+                .response
+                .on_hover_text("Used when the request doesn't set Accept-Encoding itself");
+
+                ui.horizontal(|ui| {
+                    ui.label("Repeat run concurrency:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.settings.max_concurrent_requests)
+                            .range(1..=100),
+                    );
+                })
+                .response
+                .on_hover_text(
+                    "Requests in flight at once when repeating; 1 sends them one by one",
+                );
+
+                ui.add_space(8.0);
+                ui.strong("Keyboard shortcuts");
+                ui.add_space(4.0);
+                self.render_shortcut_settings(ui);
+
+                ui.add_space(8.0);
+                ui.strong("Connection pool");
+                ui.add_space(4.0);
+
+                egui::Grid::new("pool_settings")
+                    .num_columns(2)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Max idle connections per host:");
+                        ui.horizontal(|ui| {
+                            let mut unlimited = self.settings.pool_max_idle_per_host.is_none();
+                            if ui.checkbox(&mut unlimited, "Unlimited").changed() {
+                                self.settings.pool_max_idle_per_host =
+                                    if unlimited { None } else { Some(1) };
+                                pool_changed = true;
                             }
-                            ContentType::FormUrlEncoded => {
-                                let mut params = vec![];
-                                for field in &form_data {
-                                    if !field.key.is_empty()
-                                        && field.field_type == FormFieldType::Text
-                                    {
-                                        params.push((field.key.clone(), field.value.clone()));
-                                    }
-                                }
-                                req.form(&params)
+                            if let Some(max_idle) = &mut self.settings.pool_max_idle_per_host {
+                                pool_changed |= ui
+                                    .add(egui::DragValue::new(max_idle).range(0..=1000))
+                                    .on_hover_text("0 disables connection reuse")
+                                    .changed();
                             }
-                            ContentType::FormData => {
-                                let mut form = reqwest::multipart::Form::new();
-                                for field in form_data {
-                                    if !field.key.is_empty() {
-                                        match field.field_type {
-                                            FormFieldType::Text => {
-                                                form = form.text(field.key, field.value);
-                                            }
-                                            FormFieldType::File => {
-                                                if !field.value.is_empty() {
-                                                    if let Ok(file_content) =
-                                                        std::fs::read(&field.value)
-                                                    {
-                                                        let filename =
-                                                            std::path::Path::new(&field.value)
-                                                                .file_name()
-                                                                .and_then(|n| n.to_str())
-                                                                .unwrap_or("file")
-                                                                .to_string();
-
-                                                        let part = reqwest::multipart::Part::bytes(
-                                                            file_content,
-                                                        )
-                                                        .file_name(filename);
-                                                        form = form.part(field.key, part);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                                req.multipart(form)
+                        });
+                        ui.end_row();
+
+                        ui.label("Idle connection timeout:");
+                        ui.horizontal(|ui| {
+                            let mut never = self.settings.pool_idle_timeout_secs.is_none();
+                            if ui.checkbox(&mut never, "Never").changed() {
+                                self.settings.pool_idle_timeout_secs =
+                                    if never { None } else { Some(90) };
+                                pool_changed = true;
                             }
-                        }
-                    }
-                    HttpMethod::DELETE => client.delete(&url),
-                    HttpMethod::PATCH => {
-                        let req = client.patch(&url);
-                        match content_type {
-                            ContentType::Json => {
-                                req.body(body).header("Content-Type", "application/json")
+                            if let Some(secs) = &mut self.settings.pool_idle_timeout_secs {
+                                pool_changed |= ui
+                                    .add(egui::DragValue::new(secs).range(1..=3600).suffix(" s"))
+                                    .changed();
                             }
-                            ContentType::FormUrlEncoded => {
-                                let mut params = vec![];
-                                for field in &form_data {
-                                    if !field.key.is_empty()
-                                        && field.field_type == FormFieldType::Text
-                                    {
-                                        params.push((field.key.clone(), field.value.clone()));
-                                    }
+                        });
+                        ui.end_row();
+                    });
+
+                ui.add_space(4.0);
+                ui.label(
+                    egui::RichText::new(
+                        "Defaults: unlimited idle connections, 90 s idle timeout. \
+                         Saved values apply from startup.",
+                    )
+                    .weak(),
+                );
+                if ui.button("Reset to defaults").clicked() {
+                    self.settings.pool_max_idle_per_host = None;
+                    self.settings.pool_idle_timeout_secs = Some(90);
+                    pool_changed = true;
+                }
+            });
+
+        // Pooled connections live in the client, so it has to be rebuilt to pick these up
+        if pool_changed {
+            self.client = build_client(&self.settings);
+        }
+        self.show_settings = open;
+    }
+
+    fn render_environments(&mut self, ctx: &egui::Context) {
+        if !self.show_environments {
+            return;
+        }
+
+        let mut open = self.show_environments;
+        let switch_shortcut =
+            format_shortcut(&self.settings.shortcut(ShortcutAction::EnvironmentSwitcher));
+        egui::Window::new("Environments")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Variables of the active environment replace {{{{name}}}} in the URL, \
+                     headers, body, form fields, cookies and token. {} switches.",
+                    switch_shortcut
+                ));
+                ui.add_space(6.0);
+
+                let mut to_remove = None;
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        for (i, env) in self.environments.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                let mut active = self.active_environment == Some(i);
+                                if ui
+                                    .radio(active, "")
+                                    .on_hover_text("Make this the active environment")
+                                    .clicked()
+                                {
+                                    active = !active;
+                                    self.active_environment = active.then_some(i);
                                 }
-                                req.form(&params)
-                            }
-                            ContentType::FormData => {
-                                let mut form = reqwest::multipart::Form::new();
-                                for field in form_data {
-                                    if !field.key.is_empty() {
-                                        match field.field_type {
-                                            FormFieldType::Text => {
-                                                form = form.text(field.key, field.value);
-                                            }
-                                            FormFieldType::File => {
-                                                if !field.value.is_empty() {
-                                                    if let Ok(file_content) =
-                                                        std::fs::read(&field.value)
-                                                    {
-                                                        let filename =
-                                                            std::path::Path::new(&field.value)
-                                                                .file_name()
-                                                                .and_then(|n| n.to_str())
-                                                                .unwrap_or("file")
-                                                                .to_string();
-
-                                                        let part = reqwest::multipart::Part::bytes(
-                                                            file_content,
-                                                        )
-                                                        .file_name(filename);
-                                                        form = form.part(field.key, part);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut env.name)
+                                        .hint_text("staging")
+                                        .desired_width(200.0),
+                                );
+                                if ui.button("❌").clicked() {
+                                    to_remove = Some(i);
                                 }
-                                req.multipart(form)
-                            }
-                        }
-                    }
-                };
+                            });
+                            ui.add(
+                                egui::TextEdit::multiline(&mut env.variables)
+                                    .code_editor()
+                                    .hint_text("base_url = https://staging.example.com")
+                                    .desired_width(f32::INFINITY)
+                                    .desired_rows(3),
+                            );
 
-                // Add custom headers
-                request = request.headers(headers);
+                            ui.add_space(4.0);
+                            ui.separator();
+                            ui.add_space(4.0);
+                        }
+                    });
 
-                // Check cancellation before sending
-                if cancel_flag.load(Ordering::Relaxed) {
-                    return HttpResponse {
-                        status: "Cancelled".to_string(),
-                        headers: String::new(),
-                        body: "Request was cancelled".to_string(),
-                        is_binary: false,
-                        filename: String::new(),
-                        bytes: Vec::new(),
-                        content_type: String::new(),
+                if let Some(i) = to_remove {
+                    self.environments.remove(i);
+                    self.active_environment = match self.active_environment {
+                        Some(active) if active == i => None,
+                        Some(active) if active > i => Some(active - 1),
+                        other => other,
                     };
                 }
 
-                match request.send().await {
-                    Ok(resp) => {
-                        // Check cancellation after receiving response
-                        if cancel_flag.load(Ordering::Relaxed) {
-                            return HttpResponse {
-                                status: "Cancelled".to_string(),
-                                headers: String::new(),
-                                body: "Request was cancelled".to_string(),
-                                is_binary: false,
-                                filename: String::new(),
-                                bytes: Vec::new(),
-                                content_type: String::new(),
-                            };
-                        }
+                if ui.button("➕ Add Environment").clicked() {
+                    self.environments.push(Environment {
+                        name: format!("Environment {}", self.environments.len() + 1),
+                        variables: String::new(),
+                    });
+                }
+            });
 
-                        let status = format!(
-                            "{} {}",
-                            resp.status().as_u16(),
-                            resp.status().canonical_reason().unwrap_or("")
-                        );
-                        let headers_map = resp.headers().clone();
-                        let headers = format!("{:#?}", headers_map);
-
-                        // Detect content type
-                        let content_type = headers_map
-                            .get("content-type")
-                            .and_then(|v| v.to_str().ok())
-                            .unwrap_or("")
-                            .to_string();
-
-                        let is_binary = content_type.starts_with("image/")
-                            || content_type.starts_with("application/pdf")
-                            || content_type.starts_with("application/octet-stream")
-                            || content_type.starts_with("video/")
-                            || content_type.starts_with("audio/");
-
-                        // Extract filename from Content-Disposition or URL
-                        let filename = headers_map
-                            .get("content-disposition")
-                            .and_then(|v| v.to_str().ok())
-                            .and_then(|s| {
-                                s.split("filename=")
-                                    .nth(1)
-                                    .map(|f| f.trim_matches(|c| c == '"' || c == '\'').to_string())
-                            })
-                            .unwrap_or_else(|| {
-                                url.split('/').last().unwrap_or("download").to_string()
-                            });
+        self.show_environments = open;
+    }
 
-                        let (body, bytes) = if is_binary {
-                            match resp.bytes().await {
-                                Ok(bytes) => {
-                                    if cancel_flag.load(Ordering::Relaxed) {
-                                        return HttpResponse {
-                                            status: "Cancelled".to_string(),
-                                            headers: String::new(),
-                                            body: "Request was cancelled".to_string(),
-                                            is_binary: false,
-                                            filename: String::new(),
-                                            bytes: Vec::new(),
-                                            content_type: String::new(),
-                                        };
-                                    }
-                                    let body = format!(
-                                        "Binary file ({} bytes)\n\nContent-Type: {}",
-                                        bytes.len(),
-                                        content_type
-                                    );
-                                    (body, bytes.to_vec())
-                                }
-                                Err(e) => (format!("Error reading binary data: {}", e), Vec::new()),
-                            }
-                        } else {
-                            let body_text = resp
-                                .text()
-                                .await
-                                .unwrap_or_else(|e| format!("Error reading body: {}", e));
-
-                            if cancel_flag.load(Ordering::Relaxed) {
-                                return HttpResponse {
-                                    status: "Cancelled".to_string(),
-                                    headers: String::new(),
-                                    body: "Request was cancelled".to_string(),
-                                    is_binary: false,
-                                    filename: String::new(),
-                                    bytes: Vec::new(),
-                                    content_type: String::new(),
-                                };
-                            }
+    /// Keyboard-driven environment picker: type to filter, arrows to move,
+    /// Enter to activate, Escape to close.
+    fn render_env_switcher(&mut self, ctx: &egui::Context) {
+        let Some(switcher) = &mut self.env_switcher else {
+            return;
+        };
 
-                            // Try to pretty print JSON
-                            let body = if let Ok(json) =
-                                serde_json::from_str::<serde_json::Value>(&body_text)
-                            {
-                                serde_json::to_string_pretty(&json).unwrap_or(body_text)
-                            } else {
-                                body_text
-                            };
-                            (body, Vec::new())
-                        };
+        // Index 0 is "No environment"; the rest are environments matching the filter
+        let filter = switcher.filter.to_lowercase();
+        let mut choices: Vec<(Option<usize>, String)> = vec![(None, "No environment".to_string())];
+        choices.extend(
+            self.environments
+                .iter()
+                .enumerate()
+                .filter(|(_, env)| env.name.to_lowercase().contains(&filter))
+                .map(|(i, env)| (Some(i), env.name.clone())),
+        );
+        if !filter.is_empty() && choices.len() > 1 {
+            choices.remove(0);
+        }
 
-                        HttpResponse {
-                            status,
-                            headers,
-                            body,
-                            is_binary,
-                            filename,
-                            bytes,
-                            content_type,
-                        }
-                    }
-                    Err(e) => {
-                        let error_msg = if e.is_timeout() {
-                            format!("Request timed out after {} seconds", timeout)
-                        } else {
-                            format!("Request failed: {}", e)
-                        };
+        let (up, down, enter, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        if down {
+            switcher.selected = (switcher.selected + 1).min(choices.len() - 1);
+        }
+        if up {
+            switcher.selected = switcher.selected.saturating_sub(1);
+        }
+        switcher.selected = switcher.selected.min(choices.len() - 1);
+
+        let mut chosen = enter.then_some(switcher.selected);
+        egui::Window::new("Switch environment")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 80.0))
+            .fixed_size(egui::vec2(320.0, 0.0))
+            .show(ctx, |ui| {
+                let input = ui.add(
+                    egui::TextEdit::singleline(&mut switcher.filter)
+                        .hint_text("Switch environment…")
+                        .desired_width(f32::INFINITY),
+                );
+                input.request_focus();
+                if input.changed() {
+                    switcher.selected = 0;
+                }
+                ui.separator();
 
-                        HttpResponse {
-                            status: "Error".to_string(),
-                            headers: String::new(),
-                            body: error_msg,
-                            is_binary: false,
-                            filename: String::new(),
-                            bytes: Vec::new(),
-                            content_type: String::new(),
-                        }
+                for (index, (env, name)) in choices.iter().enumerate() {
+                    let active = *env == self.active_environment;
+                    let label = if active {
+                        format!("{} ✔", name)
+                    } else {
+                        name.clone()
+                    };
+                    if ui
+                        .selectable_label(index == switcher.selected, label)
+                        .clicked()
+                    {
+                        chosen = Some(index);
                     }
                 }
+                if self.environments.is_empty() {
+                    ui.label(egui::RichText::new("No environments yet; add them from 🌍").weak());
+                }
             });
 
-            let _ = tx.send(response);
-        });
+        if let Some(index) = chosen {
+            self.active_environment = choices[index].0;
+            self.env_switcher = None;
+        } else if escape {
+            self.env_switcher = None;
+        }
     }
 
-    fn cancel_request(&mut self) {
-        self.cancel_flag.store(true, Ordering::Relaxed);
-        self.loading = false;
-        self.request_start_time = None;
-        self.response_body = "Request cancelled by user".to_string();
-        self.response_status = "Cancelled".to_string();
-    }
+    fn render_host_profiles(&mut self, ctx: &egui::Context) {
+        if !self.show_host_profiles {
+            return;
+        }
 
-    fn get_elapsed_time(&self) -> Option<Duration> {
-        self.request_start_time.map(|start| start.elapsed())
-    }
+        let mut open = self.show_host_profiles;
+        egui::Window::new("Host Profiles")
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Headers and auth applied to requests whose URL host matches. \
+                     Values set on the request itself take precedence.",
+                );
+                ui.add_space(6.0);
 
-    fn should_show_cancel_button(&self) -> bool {
-        if !self.loading {
-            return false;
-        }
+                let mut to_remove = None;
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        for (i, profile) in self.host_profiles.iter_mut().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label("Host:");
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut profile.host)
+                                        .hint_text("api.example.com")
+                                        .desired_width(200.0),
+                                );
+                                if ui.button("❌").clicked() {
+                                    to_remove = Some(i);
+                                }
+                            });
 
-        if let Some(elapsed) = self.get_elapsed_time() {
-            // Show cancel button if elapsed time >= timeout
-            elapsed.as_secs() >= self.request_timeout
-        } else {
-            false
-        }
+                            ui.horizontal(|ui| {
+                                ui.label("Auth:");
+                                egui::ComboBox::from_id_salt(format!("profile_auth_{}", i))
+                                    .selected_text(if profile.auth_type == AuthType::None {
+                                        "No Auth"
+                                    } else {
+                                        "Bearer Token"
+                                    })
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(
+                                            &mut profile.auth_type,
+                                            AuthType::None,
+                                            "No Auth",
+                                        );
+                                        ui.selectable_value(
+                                            &mut profile.auth_type,
+                                            AuthType::Bearer,
+                                            "Bearer Token",
+                                        );
+                                    });
+                                if profile.auth_type == AuthType::Bearer {
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut profile.bearer_token)
+                                            .hint_text("token")
+                                            .desired_width(f32::INFINITY),
+                                    );
+                                }
+                            });
+
+                            ui.add(
+                                egui::TextEdit::multiline(&mut profile.headers)
+                                    .code_editor()
+                                    .hint_text("# Key: Value")
+                                    .desired_width(f32::INFINITY)
+                                    .desired_rows(3),
+                            );
+
+                            ui.add_space(4.0);
+                            ui.separator();
+                            ui.add_space(4.0);
+                        }
+                    });
+
+                if let Some(i) = to_remove {
+                    self.host_profiles.remove(i);
+                }
+
+                if ui.button("➕ Add Profile").clicked() {
+                    // Start from the host currently in the URL bar
+                    let host = reqwest::Url::parse(self.request.url.trim())
+                        .ok()
+                        .and_then(|u| u.host_str().map(str::to_string))
+                        .unwrap_or_default();
+                    self.host_profiles.push(HostProfile {
+                        host,
+                        headers: String::new(),
+                        auth_type: AuthType::None,
+                        bearer_token: String::new(),
+                    });
+                }
+            });
+
+        self.show_host_profiles = open;
     }
 
     fn render_find_dialog(&mut self, ctx: &egui::Context) {
@@ -1228,10 +5136,7 @@ impl MyApp {
 
                 // Auto-update match count while typing
                 if find_response.changed() {
-                    let text = self.get_search_text().to_string();
-                    let matches = self.find_matches(&text, &self.find_dialog.find_text);
-                    self.find_dialog.total_matches = matches.len();
-                    self.find_dialog.current_match = if matches.is_empty() { 0 } else { 1 };
+                    self.refresh_find_results();
                 }
 
                 // Enter key to find next
@@ -1261,10 +5166,34 @@ impl MyApp {
             ui.add_space(4.0);
 
             // Options
+            let mut options_changed = false;
             ui.horizontal(|ui| {
-                ui.checkbox(&mut self.find_dialog.case_sensitive, "Case sensitive");
-                ui.checkbox(&mut self.find_dialog.whole_word, "Whole word");
+                options_changed |= ui
+                    .checkbox(&mut self.find_dialog.case_sensitive, "Case sensitive")
+                    .changed();
+                options_changed |= ui
+                    .checkbox(&mut self.find_dialog.whole_word, "Whole word")
+                    .changed();
+                options_changed |= ui
+                    .checkbox(&mut self.find_dialog.use_regex, "Regex")
+                    .on_hover_text("Replacements may use $1 or ${name} for groups")
+                    .changed();
             });
+            if self.find_dialog.context == FindContext::ResponseBody {
+                options_changed |= ui
+                    .checkbox(&mut self.find_dialog.include_headers, "Search headers too")
+                    .changed();
+            }
+            if options_changed {
+                self.refresh_find_results();
+            }
+            if let Some(e) = self.find_dialog.regex_error() {
+                ui.colored_label(
+                    egui::Color32::from_rgb(200, 100, 100),
+                    format!("Invalid regex, searching as plain text: {}", e),
+                )
+                .on_hover_text(e);
+            }
 
             ui.add_space(4.0);
 
@@ -1303,13 +5232,22 @@ impl MyApp {
                 ui.separator();
                 ui.add_space(4.0);
 
-                if self.find_dialog.total_matches > 0 {
+                let header_matches = self
+                    .find_dialog
+                    .results
+                    .iter()
+                    .filter(|r| r.body_range.is_none())
+                    .count();
+                if self.find_dialog.total_matches > 0 || header_matches > 0 {
+                    let mut summary = format!(
+                        "{} / {} matches",
+                        self.find_dialog.current_match, self.find_dialog.total_matches
+                    );
+                    if header_matches > 0 {
+                        summary.push_str(&format!(", {} in headers", header_matches));
+                    }
                     ui.label(
-                        egui::RichText::new(format!(
-                            "{} / {} matches",
-                            self.find_dialog.current_match, self.find_dialog.total_matches
-                        ))
-                        .color(egui::Color32::from_rgb(100, 200, 100)),
+                        egui::RichText::new(summary).color(egui::Color32::from_rgb(100, 200, 100)),
                     );
                 } else {
                     ui.label(
@@ -1317,13 +5255,129 @@ impl MyApp {
                             .color(egui::Color32::from_rgb(200, 100, 100)),
                     );
                 }
+
+                if !self.find_dialog.results.is_empty() {
+                    let mut selected = None;
+                    egui::ScrollArea::vertical()
+                        .id_salt("find_results")
+                        .max_height(180.0)
+                        .show(ui, |ui| {
+                            for (i, result) in self.find_dialog.results.iter().enumerate() {
+                                let is_current = result.body_range.as_ref().map(|r| r.start)
+                                    == self.find_dialog.current_match_pos
+                                    && result.body_range.is_some();
+                                let label = egui::RichText::new(format!(
+                                    "{:<14} {}",
+                                    result.location, result.snippet
+                                ))
+                                .monospace();
+                                if ui.selectable_label(is_current, label).clicked() {
+                                    selected = Some(i);
+                                }
+                            }
+                        });
+                    if let Some(i) = selected {
+                        self.select_find_result(i);
+                    }
+                }
             }
         });
     }
 
+    /// Recounts matches and rebuilds the match list after the pattern or
+    /// options change.
+    fn refresh_find_results(&mut self) {
+        let text = self.get_search_text().to_string();
+        let matches = self.find_matches(&text);
+        self.find_dialog.total_matches = matches.len();
+        self.find_dialog.current_match = if matches.is_empty() { 0 } else { 1 };
+
+        let snippet = |line: &str| -> String {
+            let line = line.trim();
+            match line.char_indices().nth(60) {
+                Some((cut, _)) => format!("{}…", &line[..cut]),
+                None => line.to_string(),
+            }
+        };
+
+        let mut results: Vec<FindResult> = matches
+            .iter()
+            .take(MAX_FIND_RESULTS)
+            .map(|range| {
+                let line_start = text[..range.start].rfind('\n').map_or(0, |i| i + 1);
+                let line_end = text[range.start..]
+                    .find('\n')
+                    .map_or(text.len(), |i| range.start + i);
+                FindResult {
+                    location: format!(
+                        "body line {}",
+                        text[..range.start].matches('\n').count() + 1
+                    ),
+                    snippet: snippet(&text[line_start..line_end]),
+                    body_range: Some(range.clone()),
+                }
+            })
+            .collect();
+
+        if self.find_dialog.include_headers && self.find_dialog.context == FindContext::ResponseBody
+        {
+            for (name, value) in &self.response_header_map {
+                let line = format!("{}: {}", name, String::from_utf8_lossy(value.as_bytes()));
+                for _ in self.find_matches(&line) {
+                    if results.len() >= MAX_FIND_RESULTS {
+                        break;
+                    }
+                    results.push(FindResult {
+                        location: format!("header {}", name),
+                        snippet: snippet(&line),
+                        body_range: None,
+                    });
+                }
+            }
+        }
+        self.find_dialog.results = results;
+    }
+
+    fn select_find_result(&mut self, index: usize) {
+        let Some(result) = self.find_dialog.results.get(index) else {
+            return;
+        };
+        let Some(range) = result.body_range.clone() else {
+            // Header matches live on the Headers tab
+            self.active_response_tab = ResponseTab::Headers;
+            return;
+        };
+        let text = self.get_search_text().to_string();
+        self.find_dialog.current_match = index + 1;
+        self.focus_match(&text, range);
+        if self.find_dialog.context == FindContext::ResponseBody {
+            self.active_response_tab = ResponseTab::Body;
+        }
+    }
+
+    /// Makes `range` the current match and scrolls it into view.
+    fn focus_match(&mut self, text: &str, range: std::ops::Range<usize>) {
+        self.find_dialog.current_match_pos = Some(range.start);
+        // A match past the collapsed preview is only visible in the full body
+        if self.find_dialog.context == FindContext::ResponseBody
+            && range.end > self.shown_body_len()
+        {
+            self.show_full_body = true;
+        }
+
+        let line_number = text[..range.start.min(text.len())]
+            .chars()
+            .filter(|c| *c == '\n')
+            .count();
+        let target_y = (line_number as f32 * self.find_dialog.line_height)
+            - self.find_dialog.line_height * 2.5;
+        self.find_dialog.target_scroll_y = Some(target_y.max(0.0));
+        self.find_dialog.scroll_to_match = true;
+    }
+
     fn get_search_text(&self) -> &str {
         match self.find_dialog.context {
-            FindContext::RequestBody => &self.body,
+            FindContext::RequestBody => &self.request.body,
             FindContext::ResponseBody => &self.response_body,
             FindContext::None => "",
         }
@@ -1331,75 +5385,21 @@ impl MyApp {
 
     fn get_search_text_mut(&mut self) -> &mut String {
         match self.find_dialog.context {
-            FindContext::RequestBody => &mut self.body,
+            FindContext::RequestBody => &mut self.request.body,
             FindContext::ResponseBody => &mut self.response_body,
-            FindContext::None => &mut self.body, // fallback
+            FindContext::None => &mut self.request.body, // fallback
         }
     }
 
-    fn find_matches(&self, text: &str, pattern: &str) -> Vec<usize> {
-        if pattern.is_empty() {
+    /// Byte ranges of the non-empty matches of the find text in `text`.
+    fn find_matches(&self, text: &str) -> Vec<std::ops::Range<usize>> {
+        let Some(re) = self.find_dialog.matcher() else {
             return Vec::new();
-        }
-
-        let mut matches = Vec::new();
-
-        if self.find_dialog.case_sensitive {
-            if self.find_dialog.whole_word {
-                // Case sensitive + whole word
-                let mut start = 0;
-                while let Some(pos) = text[start..].find(pattern) {
-                    let abs_pos = start + pos;
-                    let before =
-                        abs_pos == 0 || !text[..abs_pos].chars().last().unwrap().is_alphanumeric();
-                    let after_pos = abs_pos + pattern.len();
-                    let after = after_pos >= text.len()
-                        || !text[after_pos..].chars().next().unwrap().is_alphanumeric();
-
-                    if before && after {
-                        matches.push(abs_pos);
-                    }
-                    start = abs_pos + 1;
-                }
-            } else {
-                // Case sensitive only
-                let mut start = 0;
-                while let Some(pos) = text[start..].find(pattern) {
-                    matches.push(start + pos);
-                    start += pos + 1;
-                }
-            }
-        } else {
-            let text_lower = text.to_lowercase();
-            let pattern_lower = pattern.to_lowercase();
-
-            if self.find_dialog.whole_word {
-                // Case insensitive + whole word
-                let mut start = 0;
-                while let Some(pos) = text_lower[start..].find(&pattern_lower) {
-                    let abs_pos = start + pos;
-                    let before =
-                        abs_pos == 0 || !text[..abs_pos].chars().last().unwrap().is_alphanumeric();
-                    let after_pos = abs_pos + pattern.len();
-                    let after = after_pos >= text.len()
-                        || !text[after_pos..].chars().next().unwrap().is_alphanumeric();
-
-                    if before && after {
-                        matches.push(abs_pos);
-                    }
-                    start = abs_pos + 1;
-                }
-            } else {
-                // Case insensitive only
-                let mut start = 0;
-                while let Some(pos) = text_lower[start..].find(&pattern_lower) {
-                    matches.push(start + pos);
-                    start += pos + 1;
-                }
-            }
-        }
-
-        matches
+        };
+        re.find_iter(text)
+            .filter(|m| !m.is_empty())
+            .map(|m| m.range())
+            .collect()
     }
 
     fn find_next(&mut self) {
@@ -1408,7 +5408,7 @@ impl MyApp {
         }
 
         let text = self.get_search_text().to_string();
-        let matches = self.find_matches(&text, &self.find_dialog.find_text);
+        let matches = self.find_matches(&text);
         self.find_dialog.total_matches = matches.len();
 
         if matches.is_empty() {
@@ -1426,16 +5426,8 @@ impl MyApp {
                 self.find_dialog.current_match = 1;
             }
             if self.find_dialog.current_match > 0 {
-                let match_pos = matches[self.find_dialog.current_match - 1];
-                self.find_dialog.current_match_pos = Some(match_pos);
-
-                let line_number = text[..match_pos.min(text.len())]
-                    .chars()
-                    .filter(|c| *c == '\n')
-                    .count();
-                let target_y = (line_number as f32 * self.find_dialog.line_height)
-                    - self.find_dialog.line_height * 2.5;
-                self.find_dialog.target_scroll_y = Some(target_y.max(0.0));
+                let range = matches[self.find_dialog.current_match - 1].clone();
+                self.focus_match(&text, range);
             }
         }
 
@@ -1443,10 +5435,12 @@ impl MyApp {
     }
 
     fn replace_current(&mut self) {
+        if self.request_locked && self.find_dialog.context == FindContext::RequestBody {
+            return;
+        }
         let text = self.get_search_text().to_string();
-        let find_text = self.find_dialog.find_text.clone();
         let replace_text = self.find_dialog.replace_text.clone();
-        let matches = self.find_matches(&text, &find_text);
+        let matches = self.find_matches(&text);
 
         if matches.is_empty() || self.find_dialog.current_match == 0 {
             return;
@@ -1454,10 +5448,23 @@ impl MyApp {
 
         let match_idx = self.find_dialog.current_match - 1;
         if match_idx < matches.len() {
-            let pos = matches[match_idx];
+            let range = matches[match_idx].clone();
+            let replacement = match self.find_dialog.matcher() {
+                Some(re) if self.find_dialog.expands_groups() => {
+                    match re.captures_at(&text, range.start) {
+                        Some(caps) => {
+                            let mut expanded = String::new();
+                            caps.expand(&replace_text, &mut expanded);
+                            expanded
+                        }
+                        None => replace_text,
+                    }
+                }
+                _ => replace_text,
+            };
             let search_text = self.get_search_text_mut();
 
-            search_text.replace_range(pos..pos + find_text.len(), &replace_text);
+            search_text.replace_range(range, &replacement);
 
             // Find next after replace
             self.find_next();
@@ -1465,30 +5472,34 @@ impl MyApp {
     }
 
     fn replace_all(&mut self) {
-        let text = self.get_search_text().to_string();
-        let find_text = self.find_dialog.find_text.clone();
+        if self.request_locked && self.find_dialog.context == FindContext::RequestBody {
+            return;
+        }
+        let Some(re) = self.find_dialog.matcher() else {
+            return;
+        };
+        let expand = self.find_dialog.expands_groups();
         let replace_text = self.find_dialog.replace_text.clone();
-        let case_sensitive = self.find_dialog.case_sensitive;
-        let search_text = self.get_search_text_mut();
+        let text = self.get_search_text().to_string();
 
-        if case_sensitive {
-            *search_text = text.replace(&find_text, &replace_text);
-        } else {
-            // Case insensitive replace
-            let pattern = find_text.to_lowercase();
-            let mut result = String::new();
-            let text_lower = text.to_lowercase();
-            let mut last_pos = 0;
-
-            while let Some(pos) = text_lower[last_pos..].find(&pattern) {
-                let abs_pos = last_pos + pos;
-                result.push_str(&text[last_pos..abs_pos]);
+        // Empty matches are skipped, as in find
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for caps in re.captures_iter(&text) {
+            let whole = caps.get(0).expect("group 0 always matches");
+            if whole.is_empty() {
+                continue;
+            }
+            result.push_str(&text[last..whole.start()]);
+            if expand {
+                caps.expand(&replace_text, &mut result);
+            } else {
                 result.push_str(&replace_text);
-                last_pos = abs_pos + find_text.len();
             }
-            result.push_str(&text[last_pos..]);
-            *search_text = result;
+            last = whole.end();
         }
+        result.push_str(&text[last..]);
+        *self.get_search_text_mut() = result;
 
         self.find_dialog.current_match = 0;
         self.find_dialog.total_matches = 0;
@@ -1496,7 +5507,7 @@ impl MyApp {
 
     fn find_previous(&mut self) {
         let text = self.get_search_text().to_string();
-        let matches = self.find_matches(&text, &self.find_dialog.find_text);
+        let matches = self.find_matches(&text);
         self.find_dialog.total_matches = matches.len();
 
         if matches.is_empty() {
@@ -1509,8 +5520,8 @@ impl MyApp {
                 self.find_dialog.current_match -= 1;
             }
             if self.find_dialog.current_match > 0 {
-                self.find_dialog.current_match_pos =
-                    Some(matches[self.find_dialog.current_match - 1]);
+                let range = matches[self.find_dialog.current_match - 1].clone();
+                self.focus_match(&text, range);
             }
         }
 
@@ -1518,7 +5529,82 @@ impl MyApp {
     }
 }
 
+/// Options for launching with a prefilled request, e.g.
+/// `cat payload.json | crabipie --stdin --method POST --url https://... --send`
+#[derive(Parser)]
+#[command(name = "crabipie", about = "A minimal HTTP API client")]
+struct CliArgs {
+    /// Request URL
+    #[arg(long)]
+    url: Option<String>,
+
+    /// HTTP method (GET, POST, PUT, DELETE, PATCH, TRACE, CONNECT)
+    #[arg(short = 'X', long)]
+    method: Option<HttpMethod>,
+
+    /// Header as "Key: Value", may be repeated
+    #[arg(short = 'H', long = "header")]
+    headers: Vec<String>,
+
+    /// Request body
+    #[arg(short, long, conflicts_with = "stdin")]
+    data: Option<String>,
+
+    /// Read the request body from stdin
+    #[arg(long)]
+    stdin: bool,
+
+    /// Send the request as soon as the window opens
+    #[arg(long)]
+    send: bool,
+
+    /// Send the request, print the response to stdout and exit without opening a window
+    #[arg(long, requires = "url")]
+    headless: bool,
+}
+
+/// Sends the request described by `args` and prints the response. Status and
+/// headers go to stderr so the body can be piped on its own.
+fn run_headless(args: CliArgs) -> bool {
+    let mut app = MyApp::default();
+    app.apply_cli_args(CliArgs { send: true, ..args });
+
+    let Ok(resp) = app.rx.recv() else {
+        return false;
+    };
+
+    eprintln!("{}", resp.status);
+    eprintln!("{}", resp.headers);
+    if !resp.trailers.is_empty() {
+        eprintln!("{}", resp.trailers);
+    }
+
+    use std::io::Write;
+    let mut stdout = std::io::stdout();
+    let written = if resp.is_binary {
+        stdout.write_all(&resp.bytes)
+    } else {
+        writeln!(stdout, "{}", resp.body)
+    };
+
+    written.is_ok() && resp.status != "Error"
+}
+
 fn main() -> eframe::Result<()> {
+    let mut args = CliArgs::parse();
+    if args.stdin {
+        use std::io::Read;
+        let mut body = String::new();
+        if std::io::stdin().read_to_string(&mut body).is_ok() {
+            args.data = Some(body);
+        }
+    }
+
+    if args.headless {
+        let ok = run_headless(args);
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size((1500.0, 800.0))
@@ -1530,41 +5616,205 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         MyApp::name(),
         native_options,
-        Box::new(|cc| Ok(Box::new(MyApp::new(cc)))),
+        Box::new(|cc| Ok(Box::new(MyApp::new(cc, args)))),
     )
 }
 
-impl eframe::App for MyApp {
+impl eframe::App for MyApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if let Ok(settings) = serde_json::to_string(&self.settings) {
+            storage.set_string(SETTINGS_KEY, settings);
+        }
+        if let Ok(collections) = serde_json::to_string(&self.collections) {
+            storage.set_string(COLLECTIONS_KEY, collections);
+        }
+        if let Ok(environments) = serde_json::to_string(&self.environments) {
+            storage.set_string(ENVIRONMENTS_KEY, environments);
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Check for response
         if let Ok(resp) = self.rx.try_recv() {
+            if let Some(request) = self.pending_history.take() {
+                self.history.insert(
+                    0,
+                    HistoryEntry {
+                        request,
+                        status: resp.status.clone(),
+                        elapsed: (!resp.http_version.is_empty()).then_some(resp.elapsed),
+                        time: log_timestamp(),
+                    },
+                );
+                self.history.truncate(MAX_HISTORY_ENTRIES);
+            }
+            if resp.status == "Error" {
+                self.log(LogLevel::Error, resp.body.clone());
+            } else if !resp.http_version.is_empty() {
+                self.log(
+                    if resp.status.starts_with(['4', '5']) {
+                        LogLevel::Warn
+                    } else {
+                        LogLevel::Info
+                    },
+                    format!(
+                        "← {} in {} ms, {}{}",
+                        resp.status.trim(),
+                        resp.elapsed.as_millis(),
+                        format_size(resp.response_size),
+                        if resp.content_encoding.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" ({})", resp.content_encoding)
+                        }
+                    ),
+                );
+                if !resp.encoding_note.is_empty() {
+                    self.log(LogLevel::Warn, resp.encoding_note.clone());
+                }
+            }
             self.response_status = resp.status;
             self.response_headers = resp.headers;
+            self.response_header_map = resp.header_map;
+            // Errors and cancellations have no response to audit
+            self.security_audit = if !resp.http_version.is_empty() {
+                // Judged by where redirects ended up, not the URL requested
+                let https =
+                    reqwest::Url::parse(&resp.final_url).is_ok_and(|url| url.scheme() == "https");
+                security::audit_headers(&self.response_header_map, https)
+            } else {
+                Vec::new()
+            };
+            self.response_trailers = resp.trailers;
             self.response_body = resp.body;
+            self.response_raw_body = resp.raw_body;
+            self.markdown_override = None;
+            self.show_full_body = false;
             self.is_response_binary = resp.is_binary;
             self.response_filename = resp.filename;
             self.response_bytes = resp.bytes;
             self.response_content_type = resp.content_type;
+            self.response_location = resp.location;
+            // Cancelled and failed requests never reach the server, so they have no stats
+            self.response_metrics = (!resp.http_version.is_empty()).then_some(ResponseMetrics {
+                elapsed: resp.elapsed,
+                format_time: resp.format_time,
+                request_size: resp.request_size,
+                response_size: resp.response_size,
+                http_version: resp.http_version,
+                content_encoding: resp.content_encoding,
+                encoding_note: resp.encoding_note,
+                throttle: self.last_request.as_ref().and_then(|r| r.throttle),
+            });
+            // The client already pretty prints JSON and leaves everything else raw
+            if let Some(format) = self.response_format()
+                && self.view_pretty(format) != (format == "json")
+            {
+                self.apply_body_view();
+            }
+            // Checked against the schema of the request that was sent
+            let schema = self
+                .last_request
+                .as_ref()
+                .map(|request| request.response_schema.as_str())
+                .unwrap_or_default();
+            self.schema_violations = if schema.trim().is_empty() || self.is_response_binary {
+                None
+            } else {
+                Some(validate_json_schema(schema, &self.response_body))
+            };
+            self.response_paths = if self.is_response_binary {
+                String::new()
+            } else {
+                flatten_json_text(&self.response_body)
+            };
+            self.response_json = if self.response_paths.is_empty() {
+                None
+            } else {
+                serde_json::from_str(&self.response_body).ok()
+            };
+            self.trace_echo = self
+                .last_request
+                .as_ref()
+                .filter(|request| request.method == HttpMethod::TRACE && !self.is_response_binary)
+                .and_then(|request| trace_echo(request, &self.response_body));
+            self.tree_matches = None;
+            self.selected_json_path = None;
+            self.reveal_tree_selection = false;
+            self.body_scroll_line = None;
+            self.body_cursor = None;
+            self.index_json_lines();
             self.loading = false;
-            self.active_response_tab = ResponseTab::Body;
+            // Trailers and Paths only exist for some responses
+            let tab_still_shown = match self.active_response_tab {
+                ResponseTab::None => false,
+                ResponseTab::Trailers => !self.response_trailers.is_empty(),
+                ResponseTab::Paths => !self.response_paths.is_empty(),
+                ResponseTab::Tree => self.response_json.is_some(),
+                ResponseTab::Body | ResponseTab::Headers => true,
+            };
+            if (!self.settings.keep_response_tab && !self.polling.running()) || !tab_still_shown {
+                self.active_response_tab = ResponseTab::Body;
+            }
+        }
+
+        self.poll_body_watch();
+        self.poll_formatting();
+        self.poll_repeat_run();
+        self.tick_polling(ctx);
+
+        // Keys pressed while recording a new shortcut belong to the recorder
+        let pressed_actions: Vec<ShortcutAction> = if self.recording_shortcut.is_none() {
+            ctx.input(|i| {
+                ShortcutAction::ALL
+                    .into_iter()
+                    .filter(|&action| shortcut_pressed(i, &self.settings.shortcut(action)))
+                    .collect()
+            })
+        } else {
+            Vec::new()
+        };
+        let pressed = |action| pressed_actions.contains(&action);
+        if pressed(ShortcutAction::ToggleLayout) {
+            self.layout_mode = match self.layout_mode {
+                LayoutMode::Horizontal => LayoutMode::Vertical,
+                LayoutMode::Vertical => LayoutMode::Horizontal,
+            };
+        }
+        if pressed(ShortcutAction::FormatBody)
+            && self.request.method.has_body()
+            && self.request.content_type.is_raw()
+            && !self.formatting
+        {
+            self.prettify_json();
+        }
+        if pressed(ShortcutAction::NextRequestTab) {
+            self.switch_request_tab((self.active_request_index + 1) % self.request_tabs.len());
+        }
+        if pressed(ShortcutAction::PreviousRequestTab) {
+            let count = self.request_tabs.len();
+            self.switch_request_tab((self.active_request_index + count - 1) % count);
         }
 
         ctx.input(|i| {
-            // Ctrl + Enter to send request
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::Enter) {
-                if !self.loading && !self.url.trim().is_empty() {
-                    self.send_request();
-                }
+            if pressed(ShortcutAction::Send) && !self.loading && !self.request.url.trim().is_empty()
+            {
+                self.send_request();
+            }
+
+            if pressed(ShortcutAction::EnvironmentSwitcher) {
+                self.env_switcher = match self.env_switcher {
+                    Some(_) => None,
+                    None => Some(EnvSwitcher::default()),
+                };
             }
 
-            // Ctrl+F for find
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::F) {
+            if pressed(ShortcutAction::Find) {
                 self.find_dialog.open = true;
                 self.find_dialog.replace_mode = false;
             }
 
-            // Ctrl+H for find and replace
-            if i.modifiers.ctrl && i.key_pressed(egui::Key::H) {
+            if pressed(ShortcutAction::Replace) {
                 self.find_dialog.open = true;
                 self.find_dialog.replace_mode = true;
             }
@@ -1589,6 +5839,16 @@ impl eframe::App for MyApp {
         });
 
         self.render_find_dialog(ctx);
+        self.render_host_profiles(ctx);
+        self.render_environments(ctx);
+        self.render_env_switcher(ctx);
+        self.render_settings(ctx);
+        self.render_repeat(ctx);
+        self.render_log_panel(ctx);
+        self.render_collections_panel(ctx);
+        self.render_history_panel(ctx);
+        self.render_security_panel(ctx);
+        self.render_har_import(ctx);
 
         egui::CentralPanel::default().show(ctx, |ui| {
             // Header: Title + Layout Toggle
@@ -1606,10 +5866,72 @@ impl eframe::App for MyApp {
                             LayoutMode::Vertical => LayoutMode::Horizontal,
                         };
                     }
+                    if ui.button("⚙").on_hover_text("Settings").clicked() {
+                        self.show_settings = !self.show_settings;
+                    }
+                    ui.menu_button("HAR", |ui| {
+                        if ui.button("📥 Import…").clicked() {
+                            ui.close();
+                            self.import_har();
+                        }
+                        if ui
+                            .add_enabled(
+                                self.last_request.is_some() && !self.loading,
+                                egui::Button::new("📤 Export last exchange…"),
+                            )
+                            .clicked()
+                        {
+                            ui.close();
+                            self.export_har();
+                        }
+                        if let Some(err) = &self.har_error {
+                            ui.colored_label(egui::Color32::from_rgb(200, 100, 100), err);
+                        }
+                    });
+                    ui.toggle_value(&mut self.show_collections, "📚 Collections")
+                        .on_hover_text("Saved requests");
+                    ui.toggle_value(&mut self.show_history, "🕘 History")
+                        .on_hover_text("Requests sent this session");
+                    ui.toggle_value(&mut self.show_log, "📜 Log")
+                        .on_hover_text("Show the diagnostics log");
+                    ui.toggle_value(&mut self.show_security_audit, "🛡 Security")
+                        .on_hover_text("Check the response for common security headers");
+                    if ui
+                        .button("🔁 Repeat")
+                        .on_hover_text("Send the current request several times")
+                        .clicked()
+                    {
+                        self.show_repeat = !self.show_repeat;
+                    }
+                    let env_name = self
+                        .active_environment
+                        .and_then(|i| self.environments.get(i))
+                        .map(|env| env.name.as_str())
+                        .unwrap_or("No environment");
+                    if ui
+                        .button(format!("🌍 {}", env_name))
+                        .on_hover_text(format!(
+                            "Edit environments ({} to switch)",
+                            format_shortcut(
+                                &self.settings.shortcut(ShortcutAction::EnvironmentSwitcher)
+                            )
+                        ))
+                        .clicked()
+                    {
+                        self.show_environments = !self.show_environments;
+                    }
+                    if ui
+                        .button("Profiles")
+                        .on_hover_text("Per-host default headers and auth")
+                        .clicked()
+                    {
+                        self.show_host_profiles = !self.show_host_profiles;
+                    }
                 });
             });
 
             ui.add_space(8.0);
+            self.render_request_tabs(ui);
 
             // Request Method + URL + Send
             ui.group(|ui| {
@@ -1618,24 +5940,126 @@ impl eframe::App for MyApp {
                     ui.style_mut().spacing.interact_size.y = 30.0;
 
                     // Method dropdown
-                    egui::ComboBox::from_id_salt("method")
-                        .selected_text(format!("{:?}", self.method))
-                        .width(100.0)
-                        .show_ui(ui, |ui| {
-                            for method in &[
-                                HttpMethod::GET,
-                                HttpMethod::POST,
-                                HttpMethod::PUT,
-                                HttpMethod::DELETE,
-                                HttpMethod::PATCH,
-                            ] {
-                                ui.selectable_value(
-                                    &mut self.method,
-                                    method.clone(),
-                                    format!("{:?}", method),
-                                );
+                    ui.add_enabled_ui(!self.request_locked, |ui| {
+                        egui::ComboBox::from_id_salt("method")
+                            .selected_text(format!("{:?}", self.request.method))
+                            .width(100.0)
+                            .show_ui(ui, |ui| {
+                                for method in HttpMethod::ALL {
+                                    let hint = match method {
+                                        HttpMethod::TRACE => Some(
+                                            "The server echoes back the request it received, \
+                                             showing what proxies on the way changed",
+                                        ),
+                                        HttpMethod::CONNECT => Some(
+                                            "Asks the server, usually a proxy, to open a tunnel \
+                                             to the URL's host:port; only the status and \
+                                             headers come back",
+                                        ),
+                                        _ => None,
+                                    };
+                                    let label = format!("{:?}", method);
+                                    let option = ui.selectable_value(
+                                        &mut self.request.method,
+                                        method,
+                                        label,
+                                    );
+                                    if let Some(hint) = hint {
+                                        option.on_hover_text(hint);
+                                    }
+                                }
+                            });
+                    });
+
+                    // Lock toggle
+                    let lock_icon = if self.request_locked { "🔒" } else { "🔓" };
+                    ui.toggle_value(&mut self.request_locked, lock_icon)
+                        .on_hover_text(if self.request_locked {
+                            "Request is locked. Click to allow edits"
+                        } else {
+                            "Lock the request to prevent accidental edits"
+                        });
+
+                    // The copy's tab couldn't be shown until the response arrives
+                    ui.add_enabled_ui(!self.loading, |ui| {
+                        ui.menu_button("⧉", |ui| {
+                            ui.label("Duplicate in a new tab as:");
+                            for method in HttpMethod::ALL {
+                                if method != self.request.method
+                                    && ui.button(format!("{:?}", method)).clicked()
+                                {
+                                    self.duplicate_request_as(method);
+                                    ui.close();
+                                }
                             }
+                        })
+                        .response
+                        .on_hover_text("Duplicate this request with a different method");
+                    });
+
+                    let language = self.settings.accept_language.trim().to_string();
+                    let language_label = if language.is_empty() {
+                        "🗣".to_string()
+                    } else {
+                        format!("🗣 {}", language.split(',').next().unwrap_or_default())
+                    };
+                    ui.menu_button(language_label, |ui| {
+                        ui.label("Accept-Language for requests that don't set one:");
+                        for (preset, label) in ACCEPT_LANGUAGE_PRESETS {
+                            ui.selectable_value(
+                                &mut self.settings.accept_language,
+                                preset.to_string(),
+                                label,
+                            );
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label("Custom:");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.settings.accept_language)
+                                    .hint_text("e.g. pt-BR,pt;q=0.8")
+                                    .desired_width(160.0),
+                            );
                         });
+                    })
+                    .response
+                    .on_hover_text(if language.is_empty() {
+                        "Accept-Language: not sent".to_string()
+                    } else {
+                        format!("Accept-Language: {}", language)
+                    });
+
+                    let throttle_label = match self.request.throttle {
+                        Some(rate) => format!("🐢 {}/s", format_size(rate as usize)),
+                        None => "🐢".to_string(),
+                    };
+                    ui.menu_button(throttle_label, |ui| {
+                        let mut throttled = self.request.throttle.is_some();
+                        if ui
+                            .checkbox(&mut throttled, "Throttle upload and download")
+                            .changed()
+                        {
+                            self.request.throttle = throttled.then_some(10 * 1024);
+                        }
+                        if let Some(rate) = &mut self.request.throttle {
+                            let mut kb = (*rate / 1024).max(1);
+                            ui.add(
+                                egui::DragValue::new(&mut kb)
+                                    .range(1..=1024 * 1024)
+                                    .suffix(" KB/s"),
+                            );
+                            *rate = kb * 1024;
+                        }
+                    })
+                    .response
+                    .on_hover_text("Limit this request's transfer rate to simulate a slow client");
+
+                    if let Some(profile) = self.matching_host_profile() {
+                        ui.label(
+                            egui::RichText::new(format!("🏷 {}", profile.host.trim()))
+                                .color(egui::Color32::from_rgb(100, 200, 100)),
+                        )
+                        .on_hover_text("Host profile headers and auth are applied to this request");
+                    }
 
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                         if self.loading {
@@ -1665,28 +6089,108 @@ impl eframe::App for MyApp {
                                 }
                             }
                         } else {
+                            let label = if self.dry_run {
+                                "🧪 Dry run"
+                            } else {
+                                "📤 Send"
+                            };
                             if ui
-                                .add_sized(egui::vec2(80.0, 30.0), egui::Button::new("📤 Send"))
+                                .add_sized(egui::vec2(80.0, 30.0), egui::Button::new(label))
                                 .clicked()
                             {
                                 self.send_request();
                             }
                         }
 
-                        let response = ui.add(
-                            egui::TextEdit::singleline(&mut self.url)
-                                .desired_width(f32::INFINITY)
-                                .min_size(egui::vec2(0.0, 30.0))
-                                .hint_text(
-                                    egui::RichText::new("https://api.example.com/endpoint")
-                                        .size(18.0),
-                                )
-                                .vertical_align(egui::Align::Center)
-                                .font(egui::FontId::proportional(18.0)),
+                        let polling_label =
+                            match (self.polling.next_send, self.polling.paused_remaining) {
+                                (_, Some(remaining)) => format!("⟳ ⏸ {}s", remaining.as_secs()),
+                                (Some(next), None) => format!(
+                                    "⟳ {}s",
+                                    next.saturating_duration_since(std::time::Instant::now())
+                                        .as_secs()
+                                ),
+                                (None, None) => "⟳".to_string(),
+                            };
+                        ui.menu_button(polling_label, |ui| {
+                            let mut polling = self.polling.running();
+                            ui.horizontal(|ui| {
+                                if ui.checkbox(&mut polling, "Poll every").changed() {
+                                    self.set_polling(polling);
+                                }
+                                ui.add(
+                                    egui::DragValue::new(&mut self.polling.interval_secs)
+                                        .range(1..=3600)
+                                        .suffix(" s"),
+                                );
+                            });
+                            if self.polling.running() {
+                                ui.label(
+                                    egui::RichText::new(format!(
+                                        "{} sent; paused while you type",
+                                        self.polling.sent
+                                    ))
+                                    .weak(),
+                                );
+                            }
+                        })
+                        .response
+                        .on_hover_text("Re-send this request on an interval");
+
+                        ui.toggle_value(&mut self.dry_run, "🧪").on_hover_text(
+                            "Dry run: Send builds and previews the request without sending it",
                         );
 
-                        if ui.input(|i| i.modifiers.ctrl && i.key_pressed(egui::Key::L)) {
-                            let text_len = self.url.chars().count();
+                        if ui
+                            .add_enabled(
+                                !self.dry_run
+                                    && !self.checking_connection
+                                    && !self.resolved_url().trim().is_empty(),
+                                egui::Button::new("📡").min_size(egui::vec2(30.0, 30.0)),
+                            )
+                            .on_disabled_hover_text("Needs a URL, and is off during a dry run")
+                            .on_hover_text(
+                                "Test connection: TCP connect and HEAD to the URL's host",
+                            )
+                            .clicked()
+                        {
+                            self.start_connection_check();
+                        }
+
+                        ui.toggle_value(&mut self.settings.split_url, "⇹")
+                            .on_hover_text("Edit the base URL and path separately");
+
+                        let response = if self.settings.split_url {
+                            self.render_split_url(ui)
+                        } else {
+                            ui.add_enabled(
+                                !self.request_locked,
+                                egui::TextEdit::singleline(&mut self.request.url)
+                                    .desired_width(f32::INFINITY)
+                                    .min_size(egui::vec2(0.0, 30.0))
+                                    .hint_text(
+                                        egui::RichText::new("https://api.example.com/endpoint")
+                                            .size(18.0),
+                                    )
+                                    .vertical_align(egui::Align::Center)
+                                    .font(egui::FontId::proportional(18.0)),
+                            )
+                        };
+
+                        if self.settings.split_pasted_query
+                            && response.changed()
+                            && ui.input(|i| {
+                                i.events.iter().any(|e| matches!(e, egui::Event::Paste(_)))
+                            })
+                        {
+                            self.split_url_query();
+                        }
+
+                        let focus_url = self.settings.shortcut(ShortcutAction::FocusUrl);
+                        if self.recording_shortcut.is_none()
+                            && ui.input(|i| shortcut_pressed(i, &focus_url))
+                        {
+                            let text_len = self.request.url.chars().count();
 
                             let mut state =
                                 egui::text_edit::TextEditState::load(ui.ctx(), response.id)
@@ -1710,6 +6214,14 @@ impl eframe::App for MyApp {
                 });
             });
 
+            self.render_connection_check(ui);
+            self.render_dry_run(ui);
+
+            if self.settings.show_metrics_bar {
+                ui.add_space(4.0);
+                self.render_metrics_bar(ui);
+            }
+
             ui.add_space(8.0);
 
             match self.layout_mode {
@@ -1735,8 +6247,11 @@ impl eframe::App for MyApp {
         });
 
         // Keep repainting while loading
-        if self.loading {
+        if self.loading || self.formatting || self.checking_connection || self.repeat.running() {
             ctx.request_repaint();
+        } else if self.last_auto_send.is_some() || self.polling.running() {
+            // Keep the "auto-sent Ns ago" label ticking
+            ctx.request_repaint_after(Duration::from_secs(1));
         }
     }
 }
@@ -1755,16 +6270,632 @@ fn load_icon_from_base64() -> IconData {
     }
 }
 
+fn build_client(settings: &Settings) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder()
+        .pool_idle_timeout(settings.pool_idle_timeout_secs.map(Duration::from_secs));
+    if let Some(max_idle) = settings.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    builder.build().expect("Failed to build HTTP client")
+}
+
+/// Checks `body` against the JSON Schema in `schema`, returning one message per
+/// violation. An empty list means the body is valid.
+fn validate_json_schema(schema: &str, body: &str) -> Vec<String> {
+    let schema = match serde_json::from_str::<serde_json::Value>(schema) {
+        Ok(schema) => schema,
+        Err(e) => return vec![format!("Schema is not valid JSON: {}", e)],
+    };
+    let validator = match jsonschema::validator_for(&schema) {
+        Ok(validator) => validator,
+        Err(e) => return vec![format!("Invalid schema: {}", e)],
+    };
+    let instance = match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(instance) => instance,
+        Err(e) => return vec![format!("Response is not valid JSON: {}", e)],
+    };
+
+    validator
+        .iter_errors(&instance)
+        .map(|e| {
+            let path = e.instance_path().to_string();
+            let path = if path.is_empty() {
+                "/".to_string()
+            } else {
+                path
+            };
+            format!("{}: {}", path, e)
+        })
+        .collect()
+}
+
+/// Records in `matches` every node under `path` whose key or scalar value
+/// contains `query` (already lowercased), along with its ancestors.
+/// Returns whether anything at or below this node matched.
+fn search_json_tree(
+    value: &serde_json::Value,
+    path: &str,
+    key: Option<&str>,
+    query: &str,
+    matches: &mut TreeMatches,
+) -> bool {
+    let key_matches = key.is_some_and(|k| k.to_lowercase().contains(query));
+    let mut below = false;
+    let value_matches = match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                let child = format!("{}/{}", path, k.replace('~', "~0").replace('/', "~1"));
+                below |= search_json_tree(v, &child, Some(k), query, matches);
+            }
+            false
+        }
+        serde_json::Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                below |= search_json_tree(v, &format!("{}/{}", path, i), None, query, matches);
+            }
+            false
+        }
+        serde_json::Value::String(s) => s.to_lowercase().contains(query),
+        other => other.to_string().to_lowercase().contains(query),
+    };
+
+    if key_matches || value_matches {
+        matches.matched.insert(path.to_string());
+    }
+    if below {
+        matches.on_path.insert(path.to_string());
+    }
+    key_matches || value_matches || below
+}
+
+/// Draws one JSON node. While a search is active, branches leading to a match
+/// are forced open, all others closed, and matching nodes highlighted.
+fn render_json_node(
+    ui: &mut egui::Ui,
+    label: &str,
+    value: &serde_json::Value,
+    path: &str,
+    matches: &TreeMatches,
+    selection: &mut TreeSelection,
+) {
+    let searching = !matches.query.is_empty();
+    let is_selected = selection.selected == Some(path);
+    let selected_fill = ui.visuals().selection.bg_fill;
+    let highlighted = |text: String| {
+        let text = egui::RichText::new(text).monospace();
+        if searching && matches.matched.contains(path) {
+            text.background_color(egui::Color32::from_rgb(255, 255, 0))
+                .color(egui::Color32::BLACK)
+        } else if is_selected {
+            text.background_color(selected_fill)
+        } else {
+            text
+        }
+    };
+    let track = |response: egui::Response, selection: &mut TreeSelection| {
+        if response.clicked() || response.double_clicked() {
+            selection.clicked = Some((path.to_string(), response.double_clicked()));
+        }
+        if selection.reveal && is_selected {
+            response.scroll_to_me(Some(egui::Align::Center));
+        }
+    };
+
+    let children: Vec<(String, String, &serde_json::Value)> = match value {
+        serde_json::Value::Object(map) => map
+            .iter()
+            .map(|(k, v)| {
+                let child = format!("{}/{}", path, k.replace('~', "~0").replace('/', "~1"));
+                (k.clone(), child, v)
+            })
+            .collect(),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("[{}]", i), format!("{}/{}", path, i), v))
+            .collect(),
+        scalar => {
+            let response = ui.add(
+                egui::Label::new(highlighted(format!("{}: {}", label, scalar)))
+                    .sense(egui::Sense::click()),
+            );
+            track(response, selection);
+            return;
+        }
+    };
+
+    let summary = match value {
+        serde_json::Value::Object(_) => format!("{} {{{}}}", label, children.len()),
+        _ => format!("{} [{}]", label, children.len()),
+    };
+    let mut header = egui::CollapsingHeader::new(highlighted(summary))
+        .id_salt(("json_tree", path))
+        .default_open(path.is_empty());
+    if searching {
+        header = header.open(Some(matches.on_path.contains(path)));
+    }
+    if selection.reveal
+        && selection
+            .selected
+            .is_some_and(|selected| selected.starts_with(&format!("{}/", path)))
+    {
+        header = header.open(Some(true));
+    }
+    let shown = header.show(ui, |ui| {
+        for (key, child, v) in children.iter().take(MAX_TREE_CHILDREN) {
+            if searching && !matches.matched.contains(child) && !matches.on_path.contains(child) {
+                continue;
+            }
+            render_json_node(ui, key, v, child, matches, selection);
+        }
+        if children.len() > MAX_TREE_CHILDREN {
+            ui.label(
+                egui::RichText::new(format!(
+                    "… {} more not shown",
+                    children.len() - MAX_TREE_CHILDREN
+                ))
+                .weak(),
+            );
+        }
+    });
+    track(shown.header_response, selection);
+}
+
+/// Appends the pointer path of the node starting on each line of `value` as
+/// laid out by `serde_json::to_string_pretty`. A closing bracket's line maps
+/// to its container.
+fn json_line_paths(value: &serde_json::Value, path: &str, lines: &mut Vec<String>) {
+    let children: Vec<(String, &serde_json::Value)> = match value {
+        serde_json::Value::Object(map) if !map.is_empty() => map
+            .iter()
+            .map(|(k, v)| {
+                let child = format!("{}/{}", path, k.replace('~', "~0").replace('/', "~1"));
+                (child, v)
+            })
+            .collect(),
+        serde_json::Value::Array(items) if !items.is_empty() => items
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("{}/{}", path, i), v))
+            .collect(),
+        _ => {
+            lines.push(path.to_string());
+            return;
+        }
+    };
+    lines.push(path.to_string());
+    for (child, v) in children {
+        json_line_paths(v, &child, lines);
+    }
+    lines.push(path.to_string());
+}
+
+#[derive(Clone, Copy)]
+enum EscapeLanguage {
+    Json,
+    Rust,
+    JavaScript,
+}
+
+impl EscapeLanguage {
+    fn name(self) -> &'static str {
+        match self {
+            EscapeLanguage::Json => "JSON",
+            EscapeLanguage::Rust => "Rust",
+            EscapeLanguage::JavaScript => "JS",
+        }
+    }
+}
+
+/// Quotes `text` as a double-quoted string literal, escaping quotes,
+/// backslashes, newlines and other control characters.
+fn escape_string_literal(text: &str, language: EscapeLanguage) -> String {
+    match language {
+        EscapeLanguage::Json => serde_json::to_string(text).unwrap_or_default(),
+        EscapeLanguage::Rust => format!("{:?}", text),
+        // Valid JSON, except that older engines reject raw line/paragraph separators
+        EscapeLanguage::JavaScript => serde_json::to_string(text)
+            .unwrap_or_default()
+            .replace('\u{2028}', "\\u2028")
+            .replace('\u{2029}', "\\u2029"),
+    }
+}
+
+/// Inline text field for a rename in progress. Enter or clicking away
+/// commits, Escape cancels.
+fn rename_field(ui: &mut egui::Ui, renaming: &mut Renaming) -> Option<CollectionAction> {
+    let edit = ui.text_edit_singleline(&mut renaming.text);
+    if renaming.focus {
+        edit.request_focus();
+        renaming.focus = false;
+    }
+    if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+        Some(CollectionAction::CancelRename)
+    } else if edit.lost_focus() {
+        Some(CollectionAction::FinishRename)
+    } else {
+        None
+    }
+}
+
+/// Reads the request echoed in a TRACE response `body` and compares its
+/// headers with the ones `sent` would have gone out with.
+fn trace_echo(sent: &RequestSpec, body: &str) -> Option<TraceEcho> {
+    let message = client::parse_http_message(body)?;
+    let sent_headers = client::preview_request_headers(sent).unwrap_or_default();
+
+    let headers = message
+        .headers
+        .into_iter()
+        .map(|(name, value)| {
+            let change = match sent_headers.get(name.as_str()) {
+                None => EchoedHeader::Added,
+                Some(sent) if sent.as_bytes() == value.as_bytes() => EchoedHeader::Unchanged,
+                Some(sent) => {
+                    EchoedHeader::Changed(String::from_utf8_lossy(sent.as_bytes()).into())
+                }
+            };
+            (name, value, change)
+        })
+        .collect::<Vec<_>>();
+    let removed = sent_headers
+        .keys()
+        .filter(|name| {
+            !headers
+                .iter()
+                .any(|(echoed, _, _)| echoed.eq_ignore_ascii_case(name.as_str()))
+        })
+        .map(|name| name.to_string())
+        .collect();
+
+    Some(TraceEcho {
+        start_line: message.start_line,
+        headers,
+        removed,
+    })
+}
+
+/// `markdown` with each image turned into a link to it. The viewer would
+/// otherwise load image URLs, `file://` ones included, straight from an
+/// untrusted response.
+fn markdown_images_as_links(markdown: &str) -> std::borrow::Cow<'_, str> {
+    use pulldown_cmark::{Event, Options, Parser, Tag};
+
+    // The extensions the viewer parses with
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_DEFINITION_LIST;
+    let image_starts: Vec<usize> = Parser::new_ext(markdown, options)
+        .into_offset_iter()
+        .filter(|(event, _)| matches!(event, Event::Start(Tag::Image { .. })))
+        .map(|(_, range)| range.start)
+        .collect();
+    if image_starts.is_empty() {
+        return std::borrow::Cow::Borrowed(markdown);
+    }
+
+    // Dropping the `!` leaves a link with the same text and target
+    let mut out = String::with_capacity(markdown.len());
+    let mut last = 0;
+    for start in image_starts {
+        if markdown[start..].starts_with('!') {
+            out.push_str(&markdown[last..start]);
+            last = start + 1;
+        }
+    }
+    out.push_str(&markdown[last..]);
+    std::borrow::Cow::Owned(out)
+}
+
+/// Names the format family of a text body for the pretty/raw preference:
+/// `"json"` or `"xml"` from the Content-Type, sniffed from the text when the
+/// type is missing or plain text. Other formats have no pretty view.
+fn body_format(content_type: &str, body: &str) -> Option<&'static str> {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let mime = if essence.is_empty() || essence == "text/plain" {
+        client::detect_body_mime(body)
+    } else {
+        essence.as_str()
+    };
+    if mime.ends_with("json") {
+        Some("json")
+    } else if mime.ends_with("xml") {
+        Some("xml")
+    } else {
+        None
+    }
+}
+
+/// Flattens a JSON document into `path = value` lines, e.g. `data[0].id = 7`.
+/// Returns an empty string when the text isn't JSON.
+fn flatten_json_text(text: &str) -> String {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(text) else {
+        return String::new();
+    };
+
+    let mut lines = Vec::new();
+    flatten_json(&json, String::new(), &mut lines);
+    lines.join("\n")
+}
+
+fn flatten_json(value: &serde_json::Value, path: String, out: &mut Vec<String>) {
+    match value {
+        serde_json::Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                let is_ident = key
+                    .chars()
+                    .next()
+                    .is_some_and(|c| c.is_alphabetic() || c == '_')
+                    && key.chars().all(|c| c.is_alphanumeric() || c == '_');
+                let child_path = if !is_ident {
+                    format!("{}[{}]", path, serde_json::Value::from(key.as_str()))
+                } else if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                flatten_json(child, child_path, out);
+            }
+        }
+        serde_json::Value::Array(items) if !items.is_empty() => {
+            for (i, child) in items.iter().enumerate() {
+                flatten_json(child, format!("{}[{}]", path, i), out);
+            }
+        }
+        leaf => {
+            let path = if path.is_empty() {
+                "$".to_string()
+            } else {
+                path
+            };
+            out.push(format!("{} = {}", path, leaf));
+        }
+    }
+}
+
+/// Characters that are invisible in the editor but break JSON parsing when
+/// they appear outside a string: control characters other than whitespace,
+/// plus zero-width characters that often come along with a paste.
+fn is_invisible_noise(c: char) -> bool {
+    (c.is_control() && !matches!(c, '\n' | '\r' | '\t')) || client::is_invisible(c)
+}
+
+/// Whitespace and invisible characters before the first visible one in `body`.
+fn leading_noise(body: &str) -> &str {
+    let start = body
+        .find(|c: char| !c.is_whitespace() && !is_invisible_noise(c))
+        .unwrap_or(body.len());
+    &body[..start]
+}
+
+/// Describes a leading byte order mark or stray control characters in `body`.
+fn body_encoding_issue(body: &str) -> Option<String> {
+    if body.starts_with('\u{feff}') {
+        return Some("starts with a byte order mark (U+FEFF)".to_string());
+    }
+    let (pos, c) = body.char_indices().find(|&(_, c)| is_invisible_noise(c))?;
+    if body[..pos].trim().is_empty() {
+        Some(format!(
+            "starts with invisible character U+{:04X}",
+            c as u32
+        ))
+    } else {
+        let line = body[..pos].matches('\n').count() + 1;
+        Some(format!(
+            "contains invisible character U+{:04X} on line {}",
+            c as u32, line
+        ))
+    }
+}
+
+/// Removes invisible characters from the start of `body`. Later ones may sit
+/// inside strings on purpose, so they are left alone.
+fn strip_body_noise(body: &str) -> String {
+    let head = leading_noise(body);
+    head.chars()
+        .filter(|&c| !is_invisible_noise(c))
+        .chain(body[head.len()..].chars())
+        .collect()
+}
+
+/// Checks that `body` has the RFC 6902 shape: an array of operations, each with
+/// a known `op`, a `path`, and the `value` or `from` member that op requires.
+fn validate_json_patch(body: &str) -> Result<(), String> {
+    let json: serde_json::Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    let ops = json
+        .as_array()
+        .ok_or_else(|| "expected an array of operations".to_string())?;
+
+    for (i, op) in ops.iter().enumerate() {
+        let obj = op
+            .as_object()
+            .ok_or_else(|| format!("operation {} is not an object", i))?;
+        let name = obj
+            .get("op")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| format!("operation {} is missing \"op\"", i))?;
+        if !obj.get("path").is_some_and(|v| v.is_string()) {
+            return Err(format!("operation {} is missing \"path\"", i));
+        }
+
+        let required = match name {
+            "add" | "replace" | "test" => Some("value"),
+            "move" | "copy" => Some("from"),
+            "remove" => None,
+            other => return Err(format!("operation {} has unknown op \"{}\"", i, other)),
+        };
+        if let Some(member) = required
+            && !obj.contains_key(member)
+        {
+            return Err(format!(
+                "operation {} ({}) is missing \"{}\"",
+                i, name, member
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// True for text that parses as a JSON object or array. Bare scalars don't
+/// count, so pasting a number or word into the body is left alone.
+fn is_json_document(text: &str) -> bool {
+    matches!(
+        serde_json::from_str::<serde_json::Value>(text),
+        Ok(serde_json::Value::Object(_) | serde_json::Value::Array(_))
+    )
+}
+
+/// File extension for a saved response body, from its Content-Type, falling
+/// back to the download filename's extension.
+fn extension_for_content_type(content_type: &str, filename: &str, is_binary: bool) -> String {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    let known = match mime.as_str() {
+        "application/json" | "application/problem+json" => Some("json"),
+        "text/html" => Some("html"),
+        "application/xml" | "text/xml" => Some("xml"),
+        "text/plain" => Some("txt"),
+        "text/csv" => Some("csv"),
+        "text/css" => Some("css"),
+        "text/javascript" | "application/javascript" => Some("js"),
+        "application/pdf" => Some("pdf"),
+        "image/png" => Some("png"),
+        "image/jpeg" => Some("jpg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/svg+xml" => Some("svg"),
+        _ if mime.ends_with("+json") => Some("json"),
+        _ if mime.ends_with("+xml") => Some("xml"),
+        _ => None,
+    };
+    if let Some(ext) = known {
+        return ext.to_string();
+    }
+
+    match std::path::Path::new(filename).extension() {
+        Some(ext) if !ext.is_empty() => ext.to_string_lossy().to_string(),
+        _ if is_binary => "bin".to_string(),
+        _ => "txt".to_string(),
+    }
+}
+
+/// Parses `name = value` lines, skipping blanks and `#` comments.
+fn parse_variables(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+/// Replaces each `{{name}}` with its value. Unknown names are left as is.
+fn substitute_variables(text: &str, variables: &[(String, String)]) -> String {
+    let mut result = text.to_string();
+    for (name, value) in variables {
+        result = result.replace(&format!("{{{{{}}}}}", name), value);
+    }
+    result
+}
+
+/// Leading status code of a `"200 OK"` style status line.
+fn status_code(status: &str) -> Option<u16> {
+    status.split_whitespace().next()?.parse().ok()
+}
+
+/// Label and color for a status code's class.
+fn status_class(code: u16) -> (&'static str, egui::Color32) {
+    match code {
+        200..=299 => ("2xx Success", egui::Color32::from_rgb(100, 200, 100)),
+        300..=399 => ("3xx Redirect", egui::Color32::from_rgb(120, 180, 255)),
+        400..=499 => ("4xx Client Error", egui::Color32::from_rgb(255, 165, 0)),
+        _ => ("5xx Server Error", egui::Color32::from_rgb(200, 100, 100)),
+    }
+}
+
+/// Wall-clock time of day in UTC as `HH:MM:SS.mmm`.
+fn log_timestamp() -> String {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let day_millis = millis % 86_400_000;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        day_millis / 3_600_000,
+        day_millis / 60_000 % 60,
+        day_millis / 1000 % 60,
+        day_millis % 1000
+    )
+}
+
+fn format_size(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+
+    let size = bytes as f64;
+    if size >= MB {
+        format!("{:.1} MB", size / MB)
+    } else if size >= KB {
+        format!("{:.1} KB", size / KB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
 fn base64_decode(input: &str) -> Option<Vec<u8>> {
     use base64::Engine;
     base64::engine::general_purpose::STANDARD.decode(input).ok()
 }
 
+fn paste_files_from_clipboard() -> Result<Vec<String>, String> {
+    let mut clipboard = arboard::Clipboard::new().map_err(|e| e.to_string())?;
+
+    // Files copied in a file manager come through as a list of paths
+    if let Ok(paths) = clipboard.get().file_list()
+        && !paths.is_empty()
+    {
+        return Ok(paths.into_iter().map(|p| p.display().to_string()).collect());
+    }
+
+    // Screenshots come through as raw RGBA, so write them out as a PNG first
+    let image = clipboard
+        .get_image()
+        .map_err(|_| "clipboard has no file or image".to_string())?;
+    let buffer = image::RgbaImage::from_raw(
+        image.width as u32,
+        image.height as u32,
+        image.bytes.into_owned(),
+    )
+    .ok_or_else(|| "clipboard image is malformed".to_string())?;
+
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    let path = std::env::temp_dir().join(format!("crabipie_paste_{}.png", stamp));
+    buffer.save(&path).map_err(|e| e.to_string())?;
+
+    Ok(vec![path.display().to_string()])
+}
+
 fn highlight_json_with_search(
     text: &str,
-    search_text: &str,
+    search: Option<&regex::Regex>,
     search_pos: Option<usize>,
-    case_sensitive: bool,
 ) -> egui::text::LayoutJob {
     use egui::text::LayoutJob;
     use egui::{Color32, TextFormat};
@@ -1780,22 +6911,22 @@ fn highlight_json_with_search(
 
     let mut job = LayoutJob::default();
 
-    // First, get all search matches
-    let mut search_matches = Vec::new();
-    if !search_text.is_empty() {
-        let search_lower = search_text.to_lowercase();
-        let text_lower = text.to_lowercase();
-        let mut start = 0;
-
-        while let Some(pos) = if case_sensitive {
-            text[start..].find(search_text)
-        } else {
-            text_lower[start..].find(&search_lower)
-        } {
-            search_matches.push(start + pos);
-            start += pos + 1;
-        }
-    }
+    // First, get all search matches, ordered by start
+    let search_matches: Vec<std::ops::Range<usize>> = search
+        .map(|re| {
+            re.find_iter(text)
+                .filter(|m| !m.is_empty())
+                .map(|m| m.range())
+                .collect()
+        })
+        .unwrap_or_default();
+    // End of the match starting at `pos`, if one does
+    let match_end = |pos: usize| {
+        search_matches
+            .binary_search_by_key(&pos, |m| m.start)
+            .ok()
+            .map(|i| search_matches[i].end)
+    };
 
     let mut i = 0;
 
@@ -1839,11 +6970,10 @@ fn highlight_json_with_search(
             let mut str_pos = i;
             while str_pos < end {
                 // Check if we're at a search match position
-                let is_current_match = search_pos.map_or(false, |pos| str_pos == pos);
-                let is_any_match = search_matches.contains(&str_pos);
+                let is_current_match = search_pos == Some(str_pos);
 
-                if is_any_match && !search_text.is_empty() && str_pos + search_text.len() <= end {
-                    let token = &text[str_pos..str_pos + search_text.len()];
+                if let Some(match_end) = match_end(str_pos).filter(|&e| e <= end) {
+                    let token = &text[str_pos..match_end];
                     job.append(
                         token,
                         0.0,
@@ -1857,7 +6987,7 @@ fn highlight_json_with_search(
                             ..Default::default()
                         },
                     );
-                    str_pos += search_text.len();
+                    str_pos = match_end;
                 } else {
                     let c = text[str_pos..].chars().next().unwrap();
                     job.append(
@@ -1877,11 +7007,10 @@ fn highlight_json_with_search(
         }
 
         // Check if we're at a search match position (for non-string content)
-        let is_current_match = search_pos.map_or(false, |pos| i == pos);
-        let is_any_match = search_matches.contains(&i);
+        let is_current_match = search_pos == Some(i);
 
-        if is_any_match && !search_text.is_empty() {
-            let token = &text[i..i + search_text.len()];
+        if let Some(match_end) = match_end(i) {
+            let token = &text[i..match_end];
             job.append(
                 token,
                 0.0,
@@ -1895,7 +7024,7 @@ fn highlight_json_with_search(
                     ..Default::default()
                 },
             );
-            i += search_text.len();
+            i = match_end;
             continue;
         }
 
@@ -0,0 +1,282 @@
+//! Spot checks of a response's security headers: HSTS, CSP, X-Frame-Options,
+//! X-Content-Type-Options and Referrer-Policy.
+
+use reqwest::header::HeaderMap;
+
+/// Outcome of checking one header.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Verdict {
+    Good,
+    /// Present, or covered another way, but with a setting worth a second look
+    Weak,
+    Missing,
+    /// Doesn't apply to this response, such as HSTS over plain HTTP
+    NotApplicable,
+}
+
+#[derive(Clone, Debug)]
+pub struct HeaderCheck {
+    pub header: &'static str,
+    /// Value as received, if the header was sent
+    pub value: Option<String>,
+    pub verdict: Verdict,
+    pub note: String,
+}
+
+/// HSTS max-age below this many seconds (180 days) is flagged as short
+const MIN_HSTS_MAX_AGE: u64 = 180 * 24 * 60 * 60;
+
+/// Checks `headers` for the common security headers. `https` is whether the
+/// response came over TLS, since browsers ignore HSTS sent over plain HTTP.
+pub fn audit_headers(headers: &HeaderMap, https: bool) -> Vec<HeaderCheck> {
+    let value = |name: &str| {
+        headers
+            .get(name)
+            .map(|v| String::from_utf8_lossy(v.as_bytes()).trim().to_string())
+    };
+    let csp = value("content-security-policy");
+
+    vec![
+        check_hsts(value("strict-transport-security"), https),
+        check_csp(csp.clone(), value("content-security-policy-report-only")),
+        check_frame_options(value("x-frame-options"), csp.as_deref()),
+        check_content_type_options(value("x-content-type-options")),
+        check_referrer_policy(value("referrer-policy")),
+    ]
+}
+
+fn check_hsts(value: Option<String>, https: bool) -> HeaderCheck {
+    let (verdict, note) = match &value {
+        None if !https => (
+            Verdict::NotApplicable,
+            "Not sent; it only takes effect over HTTPS and this response came over HTTP"
+                .to_string(),
+        ),
+        None => (
+            Verdict::Missing,
+            "Browsers may still connect over plain HTTP".to_string(),
+        ),
+        Some(hsts) => {
+            let max_age = hsts.split(';').find_map(|directive| {
+                let (name, age) = directive.split_once('=')?;
+                name.trim()
+                    .eq_ignore_ascii_case("max-age")
+                    .then(|| age.trim().trim_matches('"').parse::<u64>().ok())
+                    .flatten()
+            });
+            let subdomains = hsts.to_ascii_lowercase().contains("includesubdomains");
+            match max_age {
+                None => (
+                    Verdict::Weak,
+                    "No valid max-age, so it is ignored".to_string(),
+                ),
+                Some(0) => (
+                    Verdict::Weak,
+                    "max-age=0 tells browsers to forget HSTS".to_string(),
+                ),
+                Some(age) if age < MIN_HSTS_MAX_AGE => (
+                    Verdict::Weak,
+                    format!("max-age of {} days is short; 180+ is usual", age / 86_400),
+                ),
+                Some(age) => (
+                    Verdict::Good,
+                    format!(
+                        "HTTPS enforced for {} days{}",
+                        age / 86_400,
+                        if subdomains {
+                            ", including subdomains"
+                        } else {
+                            ""
+                        }
+                    ),
+                ),
+            }
+        }
+    };
+    HeaderCheck {
+        header: "Strict-Transport-Security",
+        value,
+        verdict,
+        note,
+    }
+}
+
+fn check_csp(value: Option<String>, report_only: Option<String>) -> HeaderCheck {
+    let (verdict, note) = match (&value, &report_only) {
+        (None, Some(_)) => (
+            Verdict::Weak,
+            "Only Content-Security-Policy-Report-Only is sent, so nothing is enforced".to_string(),
+        ),
+        (None, None) => (
+            Verdict::Missing,
+            "No policy restricting where scripts, styles and frames load from".to_string(),
+        ),
+        (Some(csp), _) => {
+            let lower = csp.to_ascii_lowercase();
+            let unsafe_sources: Vec<&str> = ["'unsafe-inline'", "'unsafe-eval'"]
+                .into_iter()
+                .filter(|source| lower.contains(source))
+                .collect();
+            if unsafe_sources.is_empty() {
+                (Verdict::Good, "Policy present".to_string())
+            } else {
+                (
+                    Verdict::Weak,
+                    format!(
+                        "Allows {}, which undoes much of the XSS protection",
+                        unsafe_sources.join(" and ")
+                    ),
+                )
+            }
+        }
+    };
+    HeaderCheck {
+        header: "Content-Security-Policy",
+        value: value.or(report_only),
+        verdict,
+        note,
+    }
+}
+
+fn check_frame_options(value: Option<String>, csp: Option<&str>) -> HeaderCheck {
+    let frame_ancestors =
+        csp.is_some_and(|csp| csp.to_ascii_lowercase().contains("frame-ancestors"));
+    let (verdict, note) = match value.as_deref().map(str::to_ascii_uppercase) {
+        None if frame_ancestors => (
+            Verdict::Good,
+            "Not sent, but CSP frame-ancestors covers framing".to_string(),
+        ),
+        None => (
+            Verdict::Missing,
+            "Any site can frame the page (clickjacking)".to_string(),
+        ),
+        Some(option) if option == "DENY" || option == "SAMEORIGIN" => {
+            (Verdict::Good, "Framing restricted".to_string())
+        }
+        Some(option) if option.starts_with("ALLOW-FROM") => (
+            Verdict::Weak,
+            "ALLOW-FROM is obsolete and ignored by current browsers".to_string(),
+        ),
+        Some(_) => (
+            Verdict::Weak,
+            "Unrecognized value; only DENY and SAMEORIGIN are honored".to_string(),
+        ),
+    };
+    HeaderCheck {
+        header: "X-Frame-Options",
+        value,
+        verdict,
+        note,
+    }
+}
+
+fn check_content_type_options(value: Option<String>) -> HeaderCheck {
+    let (verdict, note) = match &value {
+        None => (
+            Verdict::Missing,
+            "Browsers may MIME-sniff responses into a different type".to_string(),
+        ),
+        Some(option) if option.eq_ignore_ascii_case("nosniff") => {
+            (Verdict::Good, "MIME sniffing disabled".to_string())
+        }
+        Some(_) => (Verdict::Weak, "Only nosniff has any effect".to_string()),
+    };
+    HeaderCheck {
+        header: "X-Content-Type-Options",
+        value,
+        verdict,
+        note,
+    }
+}
+
+fn check_referrer_policy(value: Option<String>) -> HeaderCheck {
+    // The last policy a browser understands wins
+    let policy = value
+        .as_deref()
+        .and_then(|v| v.rsplit(',').map(str::trim).find(|p| !p.is_empty()))
+        .map(str::to_ascii_lowercase);
+    let (verdict, note) = match policy.as_deref() {
+        None => (
+            Verdict::Missing,
+            "Browser default applies, usually strict-origin-when-cross-origin".to_string(),
+        ),
+        Some("unsafe-url") | Some("no-referrer-when-downgrade") => (
+            Verdict::Weak,
+            "Sends full URLs, including paths and queries, to other origins".to_string(),
+        ),
+        Some(_) => (Verdict::Good, "Referrer limited".to_string()),
+    };
+    HeaderCheck {
+        header: "Referrer-Policy",
+        value,
+        verdict,
+        note,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(*name, value.parse().unwrap());
+        }
+        map
+    }
+
+    fn verdicts(checks: &[HeaderCheck]) -> Vec<Verdict> {
+        checks.iter().map(|c| c.verdict).collect()
+    }
+
+    #[test]
+    fn flags_missing_headers() {
+        let checks = audit_headers(&HeaderMap::new(), true);
+        assert_eq!(checks.len(), 5);
+        assert!(checks.iter().all(|c| c.verdict == Verdict::Missing));
+        // HSTS can't be expected over plain HTTP
+        assert_eq!(
+            audit_headers(&HeaderMap::new(), false)[0].verdict,
+            Verdict::NotApplicable
+        );
+    }
+
+    #[test]
+    fn grades_present_headers() {
+        let good = headers(&[
+            (
+                "strict-transport-security",
+                "max-age=31536000; includeSubDomains",
+            ),
+            (
+                "content-security-policy",
+                "default-src 'self'; frame-ancestors 'none'",
+            ),
+            ("x-content-type-options", "nosniff"),
+            (
+                "referrer-policy",
+                "no-referrer, strict-origin-when-cross-origin",
+            ),
+        ]);
+        assert_eq!(
+            verdicts(&audit_headers(&good, true)),
+            vec![Verdict::Good; 5]
+        );
+
+        let weak = headers(&[
+            ("strict-transport-security", "max-age=300"),
+            (
+                "content-security-policy",
+                "script-src 'self' 'unsafe-inline'",
+            ),
+            ("x-frame-options", "ALLOW-FROM https://a.io"),
+            ("x-content-type-options", "sniff"),
+            ("referrer-policy", "unsafe-url"),
+        ]);
+        assert_eq!(
+            verdicts(&audit_headers(&weak, true)),
+            vec![Verdict::Weak; 5]
+        );
+    }
+}